@@ -0,0 +1,115 @@
+//! Compare `HTML::from_string` with the same function of the last release, which parses into an
+//! `RcDom` and walks it recursively, in time and allocations
+//!
+//! Run with `cargo bench --bench parse`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+fn allocated(size: usize) {
+    let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        allocated(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        allocated(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ITERATIONS: u32 = 50;
+
+fn document() -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html><html lang=\"en\"><head><title>Benchmark</title>\
+         <meta name=\"description\" content=\"A long page\">\
+         <meta property=\"og:title\" content=\"Benchmark\">\
+         <link rel=\"icon\" href=\"/icon.png\" sizes=\"32x32\"></head><body>",
+    );
+    for i in 0..500 {
+        html.push_str(&format!(
+            "<div class=\"section\"><h2>Section {i}</h2>\
+             <p>Some <b>bold</b> and <i>italic</i> text with <a href=\"/page/{i}\">a link</a>.</p>\
+             <ul><li>One</li><li>Two</li><li>Three</li></ul>\
+             <table><tr><td>{i}</td><td>cell</td></tr></table></div>"
+        ));
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+/// Parse and extract, returning the number of links found
+fn from_string(input: &str) -> usize {
+    webpage::HTML::from_string(input.to_string(), Some("https://example.org/".to_string()))
+        .unwrap()
+        .links
+        .len()
+}
+
+/// Parse and extract with the last release, returning the number of links found
+fn from_string_rcdom(input: &str) -> usize {
+    webpage_rcdom::HTML::from_string(input.to_string(), Some("https://example.org/".to_string()))
+        .unwrap()
+        .links
+        .len()
+}
+
+fn bench(name: &str, input: &str, parse: fn(&str) -> usize) {
+    // warm up, and use the results so the parsing can not be optimized away
+    let expected = parse(input);
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let live = LIVE.load(Ordering::Relaxed);
+    PEAK.store(live, Ordering::Relaxed);
+    let result = parse(input);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+    let peak = PEAK.load(Ordering::Relaxed) - live;
+    assert_eq!(result, expected);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert_eq!(parse(input), expected);
+    }
+    let elapsed: Duration = start.elapsed() / ITERATIONS;
+
+    println!(
+        "{:<8} {:>10.2?} per run {:>7} allocations {:>9} bytes allocated {:>9} bytes peak",
+        name, elapsed, allocations, bytes, peak
+    );
+}
+
+fn main() {
+    let input = document();
+    println!("document of {} bytes", input.len());
+    bench("arena", &input, from_string);
+    bench("rcdom", &input, from_string_rcdom);
+}
@@ -0,0 +1,892 @@
+//! Main article content, along the lines of Mozilla's Readability
+//!
+//! Paragraphs are scored on their length and number of commas, and pass their score on to their
+//! ancestors. The ancestor with the best score, corrected for link density and hints in its class
+//! and id, is taken as the article, together with siblings that score well too.
+
+use std::collections::HashMap;
+
+use url::Url;
+
+use crate::html::HTML;
+use crate::parser::{self, resolve};
+use crate::sink::{Document, NodeData, NodeId};
+use crate::text::TextWriter;
+#[cfg(feature = "curl")]
+use crate::Webpage;
+
+/// Elements that never hold article content
+const SKIP_TAGS: [&str; 16] = [
+    "aside", "button", "canvas", "dialog", "embed", "footer", "form", "iframe", "input", "nav",
+    "noscript", "object", "script", "select", "style", "svg",
+];
+
+/// Roles of elements that never hold article content
+const SKIP_ROLES: [&str; 7] = [
+    "alertdialog",
+    "banner",
+    "complementary",
+    "contentinfo",
+    "dialog",
+    "menu",
+    "navigation",
+];
+
+/// Class and id hints of page furniture, unless one of `MAYBE_CANDIDATE` matches too
+const UNLIKELY_CANDIDATE: [&str; 30] = [
+    "-ad-",
+    "ad-break",
+    "agegate",
+    "banner",
+    "breadcrumbs",
+    "combx",
+    "comment",
+    "community",
+    "consent",
+    "cookie",
+    "cover-wrap",
+    "disqus",
+    "extra",
+    "footer",
+    "gdpr",
+    "header",
+    "legends",
+    "menu",
+    "pager",
+    "pagination",
+    "popup",
+    "related",
+    "remark",
+    "replies",
+    "rss",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "social",
+    "sponsor",
+];
+
+const MAYBE_CANDIDATE: [&str; 7] = [
+    "and", "article", "body", "column", "content", "main", "shadow",
+];
+
+/// Class and id hints adding to the score
+const POSITIVE: [&str; 12] = [
+    "article", "blog", "body", "content", "entry", "h-entry", "hentry", "main", "page", "post",
+    "story", "text",
+];
+
+/// Class and id hints subtracting from the score
+const NEGATIVE: [&str; 26] = [
+    "-ad-",
+    "banner",
+    "combx",
+    "com-",
+    "comment",
+    "contact",
+    "foot",
+    "footnote",
+    "gdpr",
+    "hidden",
+    "masthead",
+    "media",
+    "meta",
+    "outbrain",
+    "promo",
+    "related",
+    "scroll",
+    "share",
+    "shopping",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "sponsor",
+    "tags",
+    "tool",
+    "widget",
+];
+
+/// Class and id hints of the author line
+const BYLINE: [&str; 5] = ["author", "byline", "dateline", "p-author", "writtenby"];
+
+/// Block elements, a \<div\> without any of these as children is scored like a paragraph
+const BLOCK_TAGS: [&str; 17] = [
+    "article",
+    "blockquote",
+    "div",
+    "dl",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "img",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+];
+
+/// Elements kept in the cleaned HTML, others are replaced by their contents
+const KEEP_TAGS: [&str; 47] = [
+    "a",
+    "abbr",
+    "article",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "code",
+    "dd",
+    "del",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "section",
+    "small",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Separators between the article title and the site name in \<title\>
+const TITLE_SEPARATORS: [&str; 7] = [" | ", " - ", " – ", " — ", " :: ", " » ", " / "];
+
+/// Paragraphs shorter than this are not scored
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Main content of an article page
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Article {
+    /// Article title, without the site name
+    pub title: Option<String>,
+    /// Author line
+    pub byline: Option<String>,
+    /// Resolved URL of the lead image
+    pub lead_image: Option<String>,
+    /// Cleaned HTML of the content: presentational elements and attributes removed, URLs resolved
+    pub content: String,
+    /// Plain text of the content, with blank lines between blocks
+    pub text_content: String,
+}
+
+impl Article {
+    /// Extract the main article content from an HTML document, resolving URLs like those of
+    /// `HTML.links`: against `<base href>`, the canonical URL or else the given URL. Returns
+    /// `None` when there is no content that looks like an article.
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::Article;
+    ///
+    /// let input = r#"<html><head><title>Parsing the web | Example</title></head><body>
+    ///     <nav><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+    ///     <div class="post"><p>Documents on the web are messy, full of menus, banners and
+    ///     footers. This paragraph is what a reader came for, and it is long enough.</p></div>
+    ///     <footer>Copyright</footer>"#;
+    /// let article = Article::parse(input, Some("https://example.org/")).unwrap();
+    /// assert_eq!(article.title, Some("Parsing the web".to_string()));
+    /// assert!(article.text_content.starts_with("Documents on the web are messy"));
+    /// assert!(!article.text_content.contains("Copyright"));
+    /// ```
+    pub fn parse(input: &str, url: Option<&str>) -> Option<Self> {
+        let document = Document::parse(input);
+        let html = HTML::from_document(&document, url.map(String::from), &mut []);
+        let base = parser::base(&html);
+
+        let tree = Tree::new(&document)?;
+        let roots = tree.content_roots()?;
+
+        let title = html
+            .opengraph
+            .properties
+            .get("title")
+            .map(|title| title.trim().to_string())
+            .or_else(|| html.title.as_deref().map(clean_title))
+            .or_else(|| tree.first_heading())
+            .filter(|title| !title.is_empty());
+
+        let byline_node = tree.byline();
+        let byline = html
+            .meta
+            .get("author")
+            .map(|author| author.trim().to_string())
+            .filter(|author| !author.is_empty() && !author.starts_with("http"))
+            .or_else(|| byline_node.map(|id| tree.inner_text(id)));
+
+        let mut writer = Writer {
+            tree: &tree,
+            base,
+            title: title.as_deref(),
+            skip: byline_node,
+            content: String::new(),
+            text: TextWriter::default(),
+            images: Vec::new(),
+        };
+        for root in roots {
+            writer.write(root);
+        }
+
+        let Writer {
+            content,
+            text,
+            images,
+            ..
+        } = writer;
+
+        let lead_image = html
+            .opengraph
+            .images
+            .first()
+            .map(|image| resolve(base, &image.url))
+            .or_else(|| images.into_iter().next());
+
+        Some(Self {
+            title,
+            byline,
+            lead_image,
+            content: content.trim_end().to_string(),
+            text_content: text.finish(),
+        })
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Webpage {
+    /// Extract the main article content of this page
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Webpage, WebpageOptions};
+    ///
+    /// let webpage = Webpage::from_url("https://example.org", WebpageOptions::default()).unwrap();
+    /// if let Some(article) = webpage.article() {
+    ///     println!("{:?} by {:?}\n{}", article.title, article.byline, article.text_content);
+    /// }
+    /// ```
+    pub fn article(&self) -> Option<Article> {
+        Article::parse(&self.http.body, Some(&self.http.url))
+    }
+}
+
+/// The body of the document with furniture removed, and text statistics per node
+struct Tree<'a> {
+    document: &'a Document,
+    body: NodeId,
+    /// Body and its descendants that were not removed, in document order
+    order: Vec<NodeId>,
+    visited: Vec<bool>,
+    /// Length of the text, with whitespace collapsed
+    text_len: Vec<usize>,
+    /// Length of the text inside links
+    link_len: Vec<usize>,
+    commas: Vec<usize>,
+}
+
+impl<'a> Tree<'a> {
+    fn new(document: &'a Document) -> Option<Self> {
+        let body = find(document, Document::ROOT, "body")?;
+        let len = document.len();
+        let mut tree = Self {
+            document,
+            body,
+            order: Vec::new(),
+            visited: vec![false; len],
+            text_len: vec![0; len],
+            link_len: vec![0; len],
+            commas: vec![0; len],
+        };
+
+        let mut stack = vec![body];
+        while let Some(id) = stack.pop() {
+            if id != body && tree.is_removed(id) {
+                continue;
+            }
+            tree.order.push(id);
+            tree.visited[id] = true;
+
+            let children_start = stack.len();
+            stack.extend(document.children(id));
+            stack[children_start..].reverse();
+        }
+
+        // children come after their parents in document order
+        for &id in tree.order.iter().rev() {
+            if let NodeData::Text(ref contents) = document.node(id).data {
+                tree.text_len[id] = collapsed_len(contents);
+                tree.commas[id] = contents.matches(',').count();
+            }
+            if document.tag_name(id) == Some("a") {
+                tree.link_len[id] = tree.text_len[id];
+            }
+            if let Some(parent) = document.parent(id) {
+                tree.text_len[parent] += tree.text_len[id];
+                tree.link_len[parent] += tree.link_len[id];
+                tree.commas[parent] += tree.commas[id];
+            }
+        }
+
+        Some(tree)
+    }
+
+    fn is_removed(&self, id: NodeId) -> bool {
+        let tag_name = match self.document.tag_name(id) {
+            Some(tag_name) => tag_name,
+            None => return false,
+        };
+        if SKIP_TAGS.contains(&tag_name) {
+            return true;
+        }
+
+        let attribute = |name| self.document.attribute(id, name);
+        let hidden = attribute("hidden").is_some()
+            || attribute("aria-hidden") == Some("true")
+            || attribute("style").map_or(false, |style| {
+                style
+                    .replace(' ', "")
+                    .to_ascii_lowercase()
+                    .contains("display:none")
+            });
+        if hidden {
+            return true;
+        }
+        if let Some(role) = attribute("role") {
+            if SKIP_ROLES.contains(&&role.to_ascii_lowercase()[..]) {
+                return true;
+            }
+        }
+
+        if ["a", "article", "main"].contains(&tag_name) {
+            return false;
+        }
+        let hints = self.hints(id);
+        UNLIKELY_CANDIDATE.iter().any(|hint| hints.contains(hint))
+            && !MAYBE_CANDIDATE.iter().any(|hint| hints.contains(hint))
+    }
+
+    /// Class and id, lower cased
+    fn hints(&self, id: NodeId) -> String {
+        let class = self.document.attribute(id, "class").unwrap_or_default();
+        let element_id = self.document.attribute(id, "id").unwrap_or_default();
+        format!("{} {}", class, element_id).to_ascii_lowercase()
+    }
+
+    /// Score for the hints in the class and id
+    fn weight(&self, id: NodeId) -> f64 {
+        let mut weight = 0.;
+        for hints in [
+            self.document.attribute(id, "class"),
+            self.document.attribute(id, "id"),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let hints = hints.to_ascii_lowercase();
+            if NEGATIVE.iter().any(|hint| hints.contains(hint)) {
+                weight -= 25.;
+            }
+            if POSITIVE.iter().any(|hint| hints.contains(hint)) {
+                weight += 25.;
+            }
+        }
+        weight
+    }
+
+    fn link_density(&self, id: NodeId) -> f64 {
+        match self.text_len[id] {
+            0 => 0.,
+            len => self.link_len[id] as f64 / len as f64,
+        }
+    }
+
+    fn is_paragraph(&self, id: NodeId) -> bool {
+        match self.document.tag_name(id) {
+            Some("p") | Some("pre") | Some("td") => true,
+            Some("div") => !self.document.children(id).any(|child| {
+                let tag_name = self.document.tag_name(child).unwrap_or_default();
+                BLOCK_TAGS.contains(&tag_name)
+            }),
+            _ => false,
+        }
+    }
+
+    /// Score paragraphs and pick the best candidate, with its siblings that score well too
+    fn content_roots(&self) -> Option<Vec<NodeId>> {
+        let mut scores: HashMap<NodeId, f64> = HashMap::new();
+
+        for &id in &self.order {
+            if !self.is_paragraph(id) || self.text_len[id] < MIN_PARAGRAPH_LENGTH {
+                continue;
+            }
+            let score = 1. + self.commas[id] as f64 + (self.text_len[id] / 100).min(3) as f64;
+
+            let mut ancestor = self.document.parent(id);
+            for level in 0..5 {
+                let parent = match ancestor {
+                    Some(parent) if self.visited[parent] => parent,
+                    _ => break,
+                };
+                let divider = match level {
+                    0 => 1.,
+                    1 => 2.,
+                    level => level as f64 * 3.,
+                };
+                *scores
+                    .entry(parent)
+                    .or_insert_with(|| self.initial_score(parent)) += score / divider;
+                ancestor = self.document.parent(parent);
+            }
+        }
+
+        let scores: HashMap<NodeId, f64> = scores
+            .into_iter()
+            .map(|(id, score)| (id, score * (1. - self.link_density(id))))
+            .collect();
+        let (mut top, top_score) = scores
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(&id, &score)| (id, score))?;
+
+        // a lone child says no more than its parent
+        while top != self.body {
+            let parent = self.document.parent(top)?;
+            if parent == self.body {
+                break;
+            }
+            let elements = self
+                .document
+                .children(parent)
+                .filter(|&child| self.visited[child] && self.document.tag_name(child).is_some());
+            if elements.count() > 1 {
+                break;
+            }
+            top = parent;
+        }
+
+        let parent = match self.document.parent(top) {
+            Some(parent) if top != self.body => parent,
+            _ => return Some(vec![top]),
+        };
+        let threshold = f64::max(10., top_score * 0.2);
+        let top_class = self.document.attribute(top, "class").unwrap_or_default();
+        let roots = self
+            .document
+            .children(parent)
+            .filter(|&sibling| {
+                if sibling == top {
+                    return true;
+                }
+                if !self.visited[sibling] || self.document.tag_name(sibling).is_none() {
+                    return false;
+                }
+                let class = self
+                    .document
+                    .attribute(sibling, "class")
+                    .unwrap_or_default();
+                let bonus = if !top_class.is_empty() && class == top_class {
+                    top_score * 0.2
+                } else {
+                    0.
+                };
+                if scores.get(&sibling).map_or(0., |score| *score) + bonus >= threshold {
+                    return true;
+                }
+                self.document.tag_name(sibling) == Some("p")
+                    && self.text_len[sibling] > 80
+                    && self.link_density(sibling) < 0.25
+            })
+            .collect();
+        Some(roots)
+    }
+
+    fn initial_score(&self, id: NodeId) -> f64 {
+        let score = match self.document.tag_name(id).unwrap_or_default() {
+            "div" => 5.,
+            "blockquote" | "pre" | "td" => 3.,
+            "address" | "dd" | "dl" | "dt" | "li" | "ol" | "ul" => -3.,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.,
+            _ => 0.,
+        };
+        score + self.weight(id)
+    }
+
+    /// Element holding the author line
+    fn byline(&self) -> Option<NodeId> {
+        self.order.iter().copied().find(|&id| {
+            let attribute = |name| self.document.attribute(id, name).unwrap_or_default();
+            let is_byline = attribute("rel") == "author"
+                || attribute("itemprop").contains("author")
+                || BYLINE.iter().any(|hint| self.hints(id).contains(hint));
+            is_byline && (1..100).contains(&self.text_len[id])
+        })
+    }
+
+    fn first_heading(&self) -> Option<String> {
+        let h1 = self
+            .order
+            .iter()
+            .find(|&&id| self.document.tag_name(id) == Some("h1"))?;
+        Some(self.inner_text(*h1))
+    }
+
+    /// Text of the node and its descendants, whitespace collapsed
+    fn inner_text(&self, id: NodeId) -> String {
+        let mut text = TextWriter::default();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if !self.visited[id] {
+                continue;
+            }
+            if let NodeData::Text(ref contents) = self.document.node(id).data {
+                text.push(contents);
+            }
+            let children_start = stack.len();
+            stack.extend(self.document.children(id));
+            stack[children_start..].reverse();
+        }
+        text.finish()
+    }
+}
+
+enum Step {
+    Enter(NodeId),
+    Exit(NodeId),
+}
+
+/// Writes the cleaned HTML and the plain text of the content
+struct Writer<'a> {
+    tree: &'a Tree<'a>,
+    base: Option<&'a Url>,
+    /// Article title, headings repeating it are left out
+    title: Option<&'a str>,
+    /// Author line, it is reported separately
+    skip: Option<NodeId>,
+    content: String,
+    text: TextWriter,
+    /// Resolved URLs of the images in the content
+    images: Vec<String>,
+}
+
+impl<'a> Writer<'a> {
+    fn write(&mut self, root: NodeId) {
+        let document = self.tree.document;
+        let mut stack = vec![Step::Enter(root)];
+
+        while let Some(step) = stack.pop() {
+            let id = match step {
+                Step::Enter(id) => id,
+                Step::Exit(id) => {
+                    let tag_name = document.tag_name(id).unwrap_or_default();
+                    self.content.push_str("</");
+                    self.content.push_str(tag_name);
+                    self.content.push('>');
+                    self.text.end(tag_name);
+                    continue;
+                }
+            };
+            if !self.tree.visited[id] || Some(id) == self.skip {
+                continue;
+            }
+
+            let tag_name = match document.node(id).data {
+                NodeData::Text(ref contents) => {
+                    if !self.text.is_preformatted() && contents.trim().is_empty() {
+                        // indentation between tags
+                        if !self.content.is_empty() && !self.content.ends_with('\n') {
+                            self.content.push('\n');
+                        }
+                    } else {
+                        escape(&mut self.content, contents, false);
+                    }
+                    self.text.push(contents);
+                    continue;
+                }
+                NodeData::Element { ref name, .. } => name.local.as_ref(),
+                _ => continue,
+            };
+            if id != root && self.is_clutter(id, tag_name) {
+                continue;
+            }
+
+            if KEEP_TAGS.contains(&tag_name) {
+                if !self.open_tag(id, tag_name) {
+                    continue;
+                }
+                self.text.start(tag_name);
+                if ["br", "hr", "img"].contains(&tag_name) {
+                    self.text.end(tag_name);
+                    continue;
+                }
+                stack.push(Step::Exit(id));
+            }
+            // push in reverse, so the first child is written first
+            let children_start = stack.len();
+            stack.extend(document.children(id).map(Step::Enter));
+            stack[children_start..].reverse();
+        }
+    }
+
+    /// Leave out link lists, negatively hinted blocks, empty paragraphs and the title
+    fn is_clutter(&self, id: NodeId, tag_name: &str) -> bool {
+        let tree = self.tree;
+        match tag_name {
+            "div" | "ol" | "section" | "table" | "ul" => {
+                let weight = tree.weight(id);
+                weight < 0. || (tree.link_density(id) > 0.5 && weight < 25.)
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                tree.weight(id) < 0.
+                    || tree.link_density(id) > 0.33
+                    || self.title == Some(&tree.inner_text(id)[..])
+            }
+            "p" => {
+                tree.text_len[id] == 0
+                    && !tree
+                        .document
+                        .children(id)
+                        .any(|child| tree.document.tag_name(child).is_some())
+            }
+            _ => false,
+        }
+    }
+
+    /// Write the opening tag with the attributes that are kept, unless the element is useless
+    fn open_tag(&mut self, id: NodeId, tag_name: &str) -> bool {
+        let document = self.tree.document;
+        let mut attributes: Vec<(&str, String)> = Vec::new();
+        match tag_name {
+            "a" => {
+                let href = document.attribute(id, "href").map(str::trim);
+                if let Some(href) = href.filter(|href| !href.starts_with("javascript:")) {
+                    attributes.push(("href", resolve(self.base, href)));
+                }
+            }
+            "img" => {
+                let src = ["src", "data-src", "data-lazy-src", "data-original"]
+                    .iter()
+                    .filter_map(|name| document.attribute(id, name))
+                    .map(str::trim)
+                    .find(|src| !src.is_empty() && !src.starts_with("data:"));
+                let src = match src {
+                    Some(src) => resolve(self.base, src),
+                    None => return false,
+                };
+                self.images.push(src.clone());
+                attributes.push(("src", src));
+                for name in ["alt", "title"] {
+                    if let Some(value) = document.attribute(id, name) {
+                        attributes.push((name, value.to_string()));
+                    }
+                }
+            }
+            "td" | "th" => {
+                for name in ["colspan", "rowspan"] {
+                    if let Some(value) = document.attribute(id, name) {
+                        attributes.push((name, value.to_string()));
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        self.content.push('<');
+        self.content.push_str(tag_name);
+        for (name, value) in attributes {
+            self.content.push(' ');
+            self.content.push_str(name);
+            self.content.push_str("=\"");
+            escape(&mut self.content, &value, true);
+            self.content.push('"');
+        }
+        self.content.push('>');
+        true
+    }
+}
+
+/// First element with the given tag name, in document order
+fn find(document: &Document, root: NodeId, tag_name: &str) -> Option<NodeId> {
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        if document.tag_name(id) == Some(tag_name) {
+            return Some(id);
+        }
+        let children_start = stack.len();
+        stack.extend(document.children(id));
+        stack[children_start..].reverse();
+    }
+    None
+}
+
+/// Length of the text with whitespace collapsed and trimmed
+fn collapsed_len(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|word| word.chars().count() + 1)
+        .sum::<usize>()
+        .saturating_sub(1)
+}
+
+/// Strip the site name from a \<title\>, when enough of a title remains
+fn clean_title(title: &str) -> String {
+    let title = title.trim();
+    for separator in TITLE_SEPARATORS {
+        if let Some((before, _)) = title.rsplit_once(separator) {
+            if before.split_whitespace().count() >= 2 {
+                return before.trim().to_string();
+            }
+        }
+    }
+    title.to_string()
+}
+
+fn escape(output: &mut String, input: &str, attribute: bool) {
+    for c in input.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' if attribute => output.push_str("&quot;"),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+    <title>The history of the bicycle - Cycling Weekly</title>
+    <meta name="author" content="Jane Doe">
+</head><body>
+    <header class="site-header"><a href="/">Cycling Weekly</a></header>
+    <div id="cookie-banner"><p>We use cookies to improve your experience, accept them all please.</p></div>
+    <div class="layout">
+        <div class="sidebar"><ul><li><a href="/a">Popular one</a></li><li><a href="/b">Popular two</a></li></ul></div>
+        <article class="post">
+            <h1>The history of the bicycle</h1>
+            <p class="byline">By Jane Doe</p>
+            <img data-src="/images/lead.jpg" alt="Draisine">
+            <p>The first verifiable claim for a practically used bicycle belongs to Karl von Drais,
+               a German civil servant, who invented his running machine in 1817.</p>
+            <p>It was the first means of transport to make use of the two-wheeler principle, and
+               it is regarded as the <a href="/wiki/draisine">archetype</a> of the bicycle.</p>
+            <pre>  wheel   wheel</pre>
+            <div class="share-tools"><a href="/share/fb">Facebook</a> <a href="/share/x">X</a></div>
+            <p>   </p>
+        </article>
+    </div>
+    <footer>Copyright Cycling Weekly</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_parse() {
+        let article = Article::parse(PAGE, Some("https://example.org/history")).unwrap();
+        assert_eq!(
+            article.title,
+            Some("The history of the bicycle".to_string())
+        );
+        assert_eq!(article.byline, Some("Jane Doe".to_string()));
+        assert_eq!(
+            article.lead_image,
+            Some("https://example.org/images/lead.jpg".to_string())
+        );
+
+        assert!(article.content.starts_with("<div>\n<article>\n<img src=\"https://example.org/images/lead.jpg\" alt=\"Draisine\">\n<p>The first"));
+        assert!(article.content.ends_with("</pre>\n</article>\n</div>"));
+        assert!(article
+            .content
+            .contains("<a href=\"https://example.org/wiki/draisine\">archetype</a>"));
+        assert!(article.content.contains("<pre>  wheel   wheel</pre>"));
+        for clutter in [
+            "cookies",
+            "Popular",
+            "Facebook",
+            "Copyright",
+            "By Jane",
+            "<h1>",
+            "class=",
+        ] {
+            assert!(!article.content.contains(clutter), "{}", clutter);
+        }
+
+        let paragraphs: Vec<&str> = article.text_content.split("\n\n").collect();
+        assert_eq!(paragraphs.len(), 3);
+        assert!(paragraphs[0].starts_with("The first verifiable claim for a practically used bicycle belongs to Karl von Drais, a German"));
+        assert!(paragraphs[1].ends_with("the archetype of the bicycle."));
+        assert_eq!(paragraphs[2], "  wheel   wheel");
+    }
+
+    #[test]
+    fn test_base_href() {
+        let input = PAGE
+            .replace(
+                "<head>",
+                r#"<head><base href="https://cdn.example.org/bikes/">"#,
+            )
+            .replace("/images/lead.jpg", "images/lead.jpg")
+            .replace("/wiki/draisine", "draisine");
+        let article = Article::parse(&input, Some("https://example.org/history")).unwrap();
+        assert_eq!(
+            article.lead_image,
+            Some("https://cdn.example.org/bikes/images/lead.jpg".to_string())
+        );
+        assert!(article
+            .content
+            .contains("<a href=\"https://cdn.example.org/bikes/draisine\">archetype</a>"));
+
+        let input = PAGE.replace(
+            "<head>",
+            r#"<head><base href="/bikes/"><meta property="og:image" content="lead.png">"#,
+        );
+        let article = Article::parse(&input, Some("https://example.org/history")).unwrap();
+        assert_eq!(
+            article.lead_image,
+            Some("https://example.org/bikes/lead.png".to_string())
+        );
+    }
+
+    #[test]
+    fn test_no_article() {
+        assert_eq!(Article::parse("<nav><a href=/>Home</a></nav>", None), None);
+        assert_eq!(clean_title("Home | Site"), "Home | Site");
+        assert_eq!(
+            clean_title("A long title - Part two | Site"),
+            "A long title - Part two"
+        );
+    }
+}
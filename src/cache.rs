@@ -0,0 +1,271 @@
+//! HTTP caching info, see [RFC 9111](https://www.rfc-editor.org/rfc/rfc9111)
+#![cfg_attr(not(feature = "curl"), allow(dead_code))]
+
+use std::time::{Duration, SystemTime};
+
+use crate::date::from_http_date;
+use crate::headers;
+
+/// Status codes that are heuristically cacheable (RFC 9110, section 15.1)
+const HEURISTICALLY_CACHEABLE: [u32; 12] =
+    [200, 203, 204, 206, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Parsed `Cache-Control` header
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct CacheControl {
+    /// Directives (lower cased) with their optional argument, in order
+    pub directives: Vec<(String, Option<String>)>,
+}
+
+impl CacheControl {
+    /// Parse a header value such as `public, max-age=3600, stale-while-revalidate="60"`
+    pub fn parse(value: &str) -> Self {
+        let directives = split_list(value)
+            .into_iter()
+            .filter_map(|directive| {
+                let (name, argument) = match directive.split_once('=') {
+                    Some((name, argument)) => (name, Some(argument.trim().trim_matches('"'))),
+                    None => (directive, None),
+                };
+                let name = name.trim().to_ascii_lowercase();
+                if name.is_empty() {
+                    None
+                } else {
+                    Some((name, argument.map(str::to_string)))
+                }
+            })
+            .collect();
+
+        Self { directives }
+    }
+
+    /// Whether the directive is present
+    pub fn contains(&self, name: &str) -> bool {
+        self.directives
+            .iter()
+            .any(|(n, _)| n.eq_ignore_ascii_case(name))
+    }
+
+    /// Argument of the directive, `Some(None)` when it is present without argument
+    pub fn get(&self, name: &str) -> Option<Option<&str>> {
+        self.directives
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, argument)| argument.as_deref())
+    }
+
+    /// Delta-seconds argument of the directive
+    pub fn seconds(&self, name: &str) -> Option<Duration> {
+        self.get(name)
+            .flatten()
+            .and_then(|argument| argument.parse().ok())
+            .map(Duration::from_secs)
+    }
+
+    /// `max-age` directive
+    pub fn max_age(&self) -> Option<Duration> {
+        self.seconds("max-age")
+    }
+
+    /// `s-maxage` directive, for shared caches
+    pub fn s_maxage(&self) -> Option<Duration> {
+        self.seconds("s-maxage")
+    }
+
+    /// `no-store` directive
+    pub fn no_store(&self) -> bool {
+        self.contains("no-store")
+    }
+
+    /// `no-cache` directive
+    pub fn no_cache(&self) -> bool {
+        self.contains("no-cache")
+    }
+
+    /// `private` directive
+    pub fn private(&self) -> bool {
+        self.contains("private")
+    }
+
+    /// `public` directive
+    pub fn public(&self) -> bool {
+        self.contains("public")
+    }
+
+    /// `must-revalidate` directive
+    pub fn must_revalidate(&self) -> bool {
+        self.contains("must-revalidate")
+    }
+
+    /// `immutable` directive
+    pub fn immutable(&self) -> bool {
+        self.contains("immutable")
+    }
+
+    /// `stale-while-revalidate` directive
+    pub fn stale_while_revalidate(&self) -> Option<Duration> {
+        self.seconds("stale-while-revalidate")
+    }
+}
+
+/// Parsed `Cache-Control` from all header lines
+pub(crate) fn cache_control(headers: &[String]) -> CacheControl {
+    let values: Vec<&str> = headers::values(headers, "cache-control").collect();
+    CacheControl::parse(&values.join(","))
+}
+
+/// HTTP-date header value
+pub(crate) fn date_header(headers: &[String], name: &str) -> Option<SystemTime> {
+    headers::value(headers, name).and_then(from_http_date)
+}
+
+/// `Age` header value
+pub(crate) fn age(headers: &[String]) -> Option<Duration> {
+    headers::value(headers, "age")
+        .and_then(|age| age.parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Freshness lifetime for a private cache (RFC 9111, section 4.2.1), falling back to the
+/// heuristic of 10% of the time since `Last-Modified`
+pub(crate) fn freshness_lifetime(
+    headers: &[String],
+    response_code: u32,
+    response_time: SystemTime,
+) -> Option<Duration> {
+    let cache_control = cache_control(headers);
+    if let Some(max_age) = cache_control.max_age() {
+        return Some(max_age);
+    }
+
+    let date = date_header(headers, "date").unwrap_or(response_time);
+    if let Some(expires) = headers::value(headers, "expires") {
+        // an invalid date, like "0", represents a time in the past
+        return Some(
+            from_http_date(expires)
+                .and_then(|expires| expires.duration_since(date).ok())
+                .unwrap_or_default(),
+        );
+    }
+
+    if HEURISTICALLY_CACHEABLE.contains(&response_code) || cache_control.public() {
+        let last_modified = date_header(headers, "last-modified")?;
+        return date.duration_since(last_modified).ok().map(|d| d / 10);
+    }
+
+    None
+}
+
+/// Current age of a response (RFC 9111, section 4.2.3)
+pub(crate) fn current_age(
+    headers: &[String],
+    request_time: SystemTime,
+    response_time: SystemTime,
+    now: SystemTime,
+) -> Duration {
+    let date = date_header(headers, "date").unwrap_or(response_time);
+    let apparent_age = response_time.duration_since(date).unwrap_or_default();
+    let response_delay = response_time
+        .duration_since(request_time)
+        .unwrap_or_default();
+    let corrected_age_value = age(headers).unwrap_or_default() + response_delay;
+    let corrected_initial_age = apparent_age.max(corrected_age_value);
+    let resident_time = now.duration_since(response_time).unwrap_or_default();
+
+    corrected_initial_age + resident_time
+}
+
+/// Split a comma separated list, skipping the commas inside quoted strings
+fn split_list(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                items.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn headers(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_cache_control() {
+        let cc = cache_control(&headers(&[
+            "Cache-Control: public, max-age=3600",
+            "cache-control: stale-while-revalidate=\"60\", no-cache",
+        ]));
+        assert!(cc.public());
+        assert!(cc.no_cache());
+        assert!(!cc.no_store());
+        assert_eq!(cc.max_age(), Some(Duration::from_secs(3600)));
+        assert_eq!(cc.stale_while_revalidate(), Some(Duration::from_secs(60)));
+        assert_eq!(cc.get("public"), Some(None));
+
+        let cc = CacheControl::parse(r#"no-cache="Set-Cookie, Authorization", max-age=60"#);
+        assert_eq!(cc.directives.len(), 2);
+        assert_eq!(cc.get("no-cache"), Some(Some("Set-Cookie, Authorization")));
+        assert_eq!(cc.max_age(), Some(Duration::from_secs(60)));
+        assert!(!cc.contains("authorization\""));
+    }
+
+    #[test]
+    fn test_freshness() {
+        // Date: 2024-02-29 12:30:00 UTC
+        let now = UNIX_EPOCH + Duration::from_secs(1_709_209_800);
+        let date = "Date: Thu, 29 Feb 2024 12:30:00 GMT";
+
+        let lifetime = |lines: &[&str], code| freshness_lifetime(&headers(lines), code, now);
+        assert_eq!(
+            lifetime(&[date, "Cache-Control: max-age=60"], 200),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            lifetime(&[date, "Expires: Thu, 29 Feb 2024 13:30:00 GMT"], 200),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(lifetime(&[date, "Expires: 0"], 200), Some(Duration::ZERO));
+        // heuristic: 10% of 10 hours since last modification
+        let last_modified = "Last-Modified: Thu, 29 Feb 2024 02:30:00 GMT";
+        assert_eq!(
+            lifetime(&[date, last_modified], 200),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(lifetime(&[date, last_modified], 302), None);
+        assert_eq!(lifetime(&[date], 200), None);
+    }
+
+    #[test]
+    fn test_current_age() {
+        let request_time = UNIX_EPOCH + Duration::from_secs(1_709_209_800);
+        let response_time = request_time + Duration::from_secs(2);
+        let now = response_time + Duration::from_secs(10);
+
+        // served from an upstream cache that held it for 100 seconds
+        let lines = headers(&["Date: Thu, 29 Feb 2024 12:30:00 GMT", "Age: 100"]);
+        let age = current_age(&lines, request_time, response_time, now);
+        assert_eq!(age, Duration::from_secs(112));
+
+        // apparent age from a Date in the past
+        let lines = headers(&["Date: Thu, 29 Feb 2024 12:25:00 GMT"]);
+        let age = current_age(&lines, request_time, response_time, now);
+        assert_eq!(age, Duration::from_secs(302 + 10));
+    }
+}
@@ -0,0 +1,168 @@
+//! Reusable client for fetching many webpages
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use curl::easy::Easy;
+
+use crate::{link_header, Request, Webpage, WebpageOptions, HTML, HTTP};
+
+/// Long-lived client sharing configuration, connections, DNS lookups and cookies between
+/// fetches
+///
+/// Curl handles are pooled, so a connection (and its TLS session) to a host is reused when
+/// fetching multiple pages from it. A client can be shared between threads.
+///
+/// ## Examples
+/// ```no_run
+/// use webpage::{Client, WebpageOptions};
+///
+/// let client = Client::new(WebpageOptions::default());
+/// for url in ["https://example.org/", "https://example.org/about"] {
+///     let info = client.fetch(url).expect("Could not read from URL");
+///     println!("{:?}", info.html.title);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Client {
+    options: WebpageOptions,
+    handles: Mutex<Vec<Easy>>,
+    /// Cookie jar, in Netscape cookie file format keyed by (domain, path, name)
+    cookies: Mutex<HashMap<(String, String, String), String>>,
+}
+
+impl Client {
+    /// Create a client with the given configuration
+    pub fn new(options: WebpageOptions) -> Self {
+        Self {
+            options,
+            handles: Mutex::new(Vec::new()),
+            cookies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Configuration of this client
+    pub fn options(&self) -> &WebpageOptions {
+        &self.options
+    }
+
+    /// Fetch a webpage from the given URL, and extract HTML info
+    pub fn fetch(&self, url: &str) -> Result<Webpage, io::Error> {
+        self.fetch_request(Request::get(url))
+    }
+
+    /// Send a custom request, and extract HTML info from the response
+    ///
+    /// The canonical URL, feed and pagination links from the HTTP `Link` header are used when the
+    /// document does not specify them.
+    pub fn fetch_request(&self, request: Request) -> Result<Webpage, io::Error> {
+        let http = self.send(request)?;
+
+        let mut html = HTML::from_string(http.body.clone(), Some(http.url.clone()))?;
+        link_header::merge(&mut html, &http.link_header(), &http.url);
+
+        Ok(Webpage {
+            http,
+            html,
+            manifest: None,
+        })
+    }
+
+    /// Send a custom request
+    pub fn send(&self, request: Request) -> Result<HTTP, io::Error> {
+        self.send_raw(request).map(|(http, _)| http)
+    }
+
+    /// Send a custom request, returns the raw body bytes alongside
+    pub(crate) fn send_raw(&self, request: Request) -> Result<(HTTP, Vec<u8>), io::Error> {
+        let mut handle = self
+            .handles
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()
+            .unwrap_or_else(Easy::new);
+
+        // return the handle to the pool on errors too, to keep its connections
+        let result = self.perform(&mut handle, &request);
+
+        self.handles
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(handle);
+
+        result
+    }
+
+    /// Perform the request on a pooled handle, with the cookies of the shared jar
+    fn perform(&self, handle: &mut Easy, request: &Request) -> Result<(HTTP, Vec<u8>), io::Error> {
+        // keeps the connection cache, DNS cache and cookies of the handle
+        handle.reset();
+
+        // enable the cookie engine, and replace the cookies the handle kept from earlier requests
+        // with the shared cookie jar
+        handle.cookie_file("")?;
+        handle.cookie_list("ALL")?;
+        let loaded = self.cookies();
+        for cookie in loaded.iter() {
+            handle.cookie_list(cookie)?;
+        }
+
+        let result = HTTP::perform(handle, request, &self.options);
+
+        if let Ok(list) = handle.cookies() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs());
+            let mut jar = self.cookies.lock().unwrap_or_else(PoisonError::into_inner);
+
+            // cookies deleted by the response are missing from the list of the handle, so drop
+            // the loaded cookies first, unless another request changed them meanwhile
+            for cookie in loaded {
+                if let Some((key, _)) = parse_cookie(&cookie) {
+                    if jar.get(&key) == Some(&cookie) {
+                        jar.remove(&key);
+                    }
+                }
+            }
+            for cookie in list.iter() {
+                let cookie = String::from_utf8_lossy(cookie).into_owned();
+                if let Some((key, expires)) = parse_cookie(&cookie) {
+                    // an expiry of 0 marks a session cookie
+                    if expires == 0 || expires > now {
+                        jar.insert(key, cookie);
+                    } else {
+                        jar.remove(&key);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// All cookies in the jar, in Netscape cookie file format
+    pub fn cookies(&self) -> Vec<String> {
+        self.cookies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Key (domain, path, name) and expiry of a cookie in Netscape cookie file format
+fn parse_cookie(cookie: &str) -> Option<((String, String, String), u64)> {
+    let fields: Vec<&str> = cookie.split('\t').collect();
+    if fields.len() < 7 {
+        return None;
+    }
+    let key = (
+        fields[0].to_string(),
+        fields[2].to_string(),
+        fields[5].to_string(),
+    );
+    Some((key, fields[4].parse().unwrap_or(0)))
+}
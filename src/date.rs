@@ -0,0 +1,281 @@
+//! Conversion of timestamps to and from their textual representations
+#![cfg_attr(not(feature = "curl"), allow(dead_code))]
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Format a timestamp as ISO 8601 in UTC with millisecond precision, e.g.
+/// `2024-02-29T12:30:00.000Z`
+pub(crate) fn to_iso8601(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Parse a HTTP-date (RFC 9110, section 5.6.7), e.g. `Thu, 29 Feb 2024 12:30:00 GMT`
+pub(crate) fn from_http_date(input: &str) -> Option<SystemTime> {
+    httpdate::parse_http_date(input.trim()).ok()
+}
+
+/// Parse a RFC 822 / RFC 2822 date as found in RSS feeds, e.g. `Thu, 29 Feb 2024 13:30:00 +0100`
+///
+/// The day of week and seconds are optional, two digit years and obsolete zone names are
+/// accepted.
+pub(crate) fn from_rfc2822(input: &str) -> Option<SystemTime> {
+    let input = input.trim();
+    // skip the day of week
+    let input = input.split_once(',').map_or(input, |(_, rest)| rest);
+    let mut parts = input.split_whitespace();
+
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = parts.next()?.get(..3)?.to_ascii_lowercase();
+    let month = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ]
+    .iter()
+    .position(|name| *name == month)? as u32
+        + 1;
+    let year: i64 = match parts.next()? {
+        year if year.len() == 2 => year
+            .parse::<i64>()
+            .ok()
+            .map(|y| y + if y < 50 { 2000 } else { 1900 })?,
+        year => year.parse().ok()?,
+    };
+    let mut clock = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<i64>().ok());
+    let hours = clock.next()??;
+    let minutes = clock.next()??;
+    let seconds = clock.next().unwrap_or(Some(0))?;
+
+    let offset_minutes = match parts.next().unwrap_or("GMT") {
+        zone if zone.starts_with(['+', '-']) && zone.len() == 5 => {
+            let value: i64 = zone[1..].parse().ok()?;
+            let minutes = value / 100 * 60 + value % 100;
+            if zone.starts_with('-') {
+                -minutes
+            } else {
+                minutes
+            }
+        }
+        zone => match &zone.to_ascii_uppercase()[..] {
+            "EDT" => -4 * 60,
+            "EST" | "CDT" => -5 * 60,
+            "CST" | "MDT" => -6 * 60,
+            "MST" | "PDT" => -7 * 60,
+            "PST" => -8 * 60,
+            // GMT, UT, Z and unknown military zones
+            _ => 0,
+        },
+    };
+
+    let secs = timestamp(year, month, day, hours, minutes, seconds)? - offset_minutes * 60;
+    let secs = u64::try_from(secs).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Parse an ISO 8601 / RFC 3339 timestamp such as `2024-02-29T12:30:00Z`,
+/// `2024-02-29T13:30:00.5+01:00` or a plain date `2024-02-29`
+pub(crate) fn from_iso8601(input: &str) -> Option<SystemTime> {
+    let input = input.trim();
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = input.get(range)?;
+        if digits.bytes().all(|b| b.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    };
+
+    let year = number(0..4)?;
+    if input.get(4..5)? != "-" || input.get(7..8)? != "-" {
+        return None;
+    }
+    let month = number(5..7)?;
+    let day = number(8..10)?;
+    let (mut hours, mut minutes, mut seconds) = (0, 0, 0);
+    let mut offset_secs = 0;
+    let mut nanos = 0;
+
+    let rest = &input[10..];
+    if !rest.is_empty() {
+        if !rest.starts_with(['T', 't', ' ']) {
+            return None;
+        }
+        let time = &rest[1..];
+        let (clock, offset) = match time.find(['Z', 'z', '+', '-']) {
+            Some(pos) => time.split_at(pos),
+            None => (time, ""),
+        };
+        let (clock, fraction) = clock.split_once('.').unwrap_or((clock, ""));
+        let mut parts = clock.split(':').map(|part| part.parse::<i64>().ok());
+        hours = parts.next()??;
+        minutes = parts.next()??;
+        seconds = parts.next().unwrap_or(Some(0))?;
+        if !fraction.is_empty() {
+            if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let digits = &fraction[..fraction.len().min(9)];
+            nanos = digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32);
+        }
+
+        if offset.starts_with(['+', '-']) {
+            let offset_clock = &offset[1..];
+            let (hours, minutes) = match offset_clock.split_once(':') {
+                Some(parts) => parts,
+                None if offset_clock.len() == 4 && offset_clock.is_ascii() => {
+                    offset_clock.split_at(2)
+                }
+                None => (offset_clock, "0"),
+            };
+            let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+            if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+                return None;
+            }
+            // local time = UTC + offset
+            offset_secs =
+                (hours * 3600 + minutes * 60) * if offset.starts_with('+') { 1 } else { -1 };
+        }
+    }
+
+    let secs = timestamp(year, month as u32, day as u32, hours, minutes, seconds)? - offset_secs;
+    let secs = u64::try_from(secs).ok()?;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Seconds since the unix epoch of a date and time in UTC, if every field is in range
+fn timestamp(
+    year: i64,
+    month: u32,
+    day: u32,
+    hours: i64,
+    minutes: i64,
+    seconds: i64,
+) -> Option<i64> {
+    let in_range = (0..=9999).contains(&year)
+        && (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && (0..=23).contains(&hours)
+        && (0..=59).contains(&minutes)
+        // allow a leap second
+        && (0..=60).contains(&seconds);
+    if !in_range {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Convert a date in the proleptic Gregorian calendar to days since the unix epoch
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// Convert days since the unix epoch to a (year, month, day) date in the proleptic Gregorian
+/// calendar, see <https://howardhinnant.github.io/date_algorithms.html>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(to_iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+
+        let leap_day = UNIX_EPOCH + Duration::from_millis(1_709_209_800_123);
+        assert_eq!(to_iso8601(leap_day), "2024-02-29T12:30:00.123Z");
+    }
+
+    #[test]
+    fn test_from_iso8601() {
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_209_800);
+        assert_eq!(from_iso8601("2024-02-29T12:30:00Z"), Some(leap_day));
+        assert_eq!(from_iso8601("2024-02-29T13:30:00+01:00"), Some(leap_day));
+        assert_eq!(from_iso8601("2024-02-29T08:30-04:00"), Some(leap_day));
+        assert_eq!(from_iso8601("2024-02-29T18:00+0530"), Some(leap_day));
+        assert_eq!(
+            from_iso8601("2024-02-29T12:30:00.250Z"),
+            Some(leap_day + Duration::from_millis(250))
+        );
+        assert_eq!(
+            from_iso8601("2024-02-29"),
+            Some(leap_day - Duration::from_secs(45000))
+        );
+        assert_eq!(from_iso8601("29 Feb 2024"), None);
+
+        // malformed input is rejected instead of panicking
+        assert_eq!(from_iso8601("2024-02-29T12:30:00.12345678é"), None);
+        assert_eq!(from_iso8601("2024-02-29T99999999999999999:00Z"), None);
+        assert_eq!(
+            from_iso8601("2024-02-29T12:30:00+99999999999999999:00"),
+            None
+        );
+        assert_eq!(from_iso8601("2024-02-29T12:30:00+1é1"), None);
+        assert_eq!(from_iso8601("2024-13-01"), None);
+        assert_eq!(from_iso8601("2024-02-29T24:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_from_rfc2822() {
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_209_800);
+        assert_eq!(
+            from_rfc2822("Thu, 29 Feb 2024 12:30:00 GMT"),
+            Some(leap_day)
+        );
+        assert_eq!(from_rfc2822("29 Feb 2024 13:30:00 +0100"), Some(leap_day));
+        assert_eq!(
+            from_rfc2822("Thu, 29 February 24 07:30 EST"),
+            Some(leap_day)
+        );
+        assert_eq!(
+            from_rfc2822("Thu, 29 Feb 2024 04:30:00 -0800"),
+            Some(leap_day)
+        );
+        assert_eq!(from_rfc2822("2024-02-29T12:30:00Z"), None);
+
+        // malformed input is rejected instead of panicking
+        assert_eq!(
+            from_rfc2822("Thu, 29 Feb 99999999999999999 12:30:00 GMT"),
+            None
+        );
+        assert_eq!(
+            from_rfc2822("Thu, 29 Feb 2024 99999999999999999:30:00 GMT"),
+            None
+        );
+        assert_eq!(from_rfc2822("Thu, 32 Feb 2024 12:30:00 GMT"), None);
+        assert_eq!(from_rfc2822("Thu, 29 Fé 2024 12:30:00 GMT"), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Custom extraction hooks, run during the traversal of the document
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use html5ever::Attribute;
+
+use crate::html::HTML;
+
+/// Part of the document a node is in
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Segment {
+    /// Outside of \<head\> and \<body\>
+    None,
+    Head,
+    Body,
+}
+
+/// Collect custom data from a document
+///
+/// The callbacks are invoked in document order, after the built-in processing of the node.
+/// Results can be stored in `HTML.extensions`.
+///
+/// ## Examples
+/// ```
+/// use webpage::{Element, Extractor, Segment, HTML};
+///
+/// struct Prices(Vec<String>);
+///
+/// impl Extractor for Prices {
+///     fn element(&mut self, element: &Element, _html: &mut HTML) {
+///         if element.segment() == Segment::Body {
+///             self.0.extend(element.attribute("data-price").map(String::from));
+///         }
+///     }
+/// }
+///
+/// let input = r#"<body><span data-price="9.99">Book</span><span data-price="4.50">Pen</span>"#;
+/// let mut prices = Prices(Vec::new());
+/// let mut html = HTML::from_string_with(input.to_string(), None, &mut [&mut prices]).unwrap();
+/// assert_eq!(prices.0, vec!["9.99", "4.50"]);
+///
+/// html.extensions.insert(prices.0);
+/// assert_eq!(html.extensions.get::<Vec<String>>().unwrap().len(), 2);
+/// ```
+pub trait Extractor {
+    /// Called for every element
+    fn element(&mut self, _element: &Element, _html: &mut HTML) {}
+
+    /// Called for every text node inside an element
+    fn text(&mut self, _text: &Text, _html: &mut HTML) {}
+}
+
+/// Element passed to an [`Extractor`]
+pub struct Element<'a> {
+    pub(crate) tag_name: &'a str,
+    pub(crate) attrs: &'a [Attribute],
+    pub(crate) segment: Segment,
+    pub(crate) ancestors: &'a [&'a str],
+}
+
+impl<'a> Element<'a> {
+    /// Local tag name, lower cased
+    pub fn tag_name(&self) -> &'a str {
+        self.tag_name
+    }
+
+    /// Value of the attribute with the given local name
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attrs
+            .iter()
+            .find(|attr| attr.name.local.as_ref() == name)
+            .map(|attr| attr.value.as_ref())
+    }
+
+    /// All attributes as (local name, value)
+    pub fn attributes(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.attrs
+            .iter()
+            .map(|attr| (attr.name.local.as_ref(), attr.value.as_ref()))
+    }
+
+    pub fn segment(&self) -> Segment {
+        self.segment
+    }
+
+    /// Tag names of the enclosing elements, outermost first
+    pub fn ancestors(&self) -> &'a [&'a str] {
+        self.ancestors
+    }
+}
+
+/// Text node passed to an [`Extractor`]
+pub struct Text<'a> {
+    pub(crate) contents: &'a str,
+    pub(crate) segment: Segment,
+    pub(crate) ancestors: &'a [&'a str],
+}
+
+impl<'a> Text<'a> {
+    /// Contents, as in the document (not trimmed)
+    pub fn contents(&self) -> &'a str {
+        self.contents
+    }
+
+    /// Tag name of the element containing the text
+    pub fn parent(&self) -> &'a str {
+        self.ancestors.last().copied().unwrap_or_default()
+    }
+
+    pub fn segment(&self) -> Segment {
+        self.segment
+    }
+
+    /// Tag names of the enclosing elements, outermost first
+    pub fn ancestors(&self) -> &'a [&'a str] {
+        self.ancestors
+    }
+}
+
+/// Typed storage for custom data, holding at most one value per type
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Store a value, returning the previous value of the same type
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<Arc<T>> {
+        self.map
+            .insert(TypeId::of::<T>(), Arc::new(value))
+            .and_then(|previous| previous.downcast().ok())
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    /// Mutable access, unless the value is shared with a clone of these extensions
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        Arc::get_mut(self.map.get_mut(&TypeId::of::<T>())?)?.downcast_mut()
+    }
+
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<Arc<T>> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        elements: Vec<(String, Segment, String)>,
+        texts: Vec<(String, String)>,
+    }
+
+    impl Extractor for Recorder {
+        fn element(&mut self, element: &Element, _html: &mut HTML) {
+            let ancestors = element.ancestors().join(">");
+            let tag_name = element.tag_name().to_string();
+            self.elements.push((tag_name, element.segment(), ancestors));
+        }
+
+        fn text(&mut self, text: &Text, html: &mut HTML) {
+            if text.parent() == "h1" {
+                html.extensions.insert(text.contents().to_string());
+            }
+            self.texts
+                .push((text.ancestors().join(">"), text.contents().to_string()));
+        }
+    }
+
+    #[test]
+    fn test_callbacks() {
+        let input = "<html><head><title>T</title></head><body><h1 class=x>Head</h1><p>A <b>B</b>";
+        let mut recorder = Recorder::default();
+        let html = HTML::from_string_with(input.to_string(), None, &mut [&mut recorder]).unwrap();
+
+        assert_eq!(html.title, Some("T".to_string()));
+        assert_eq!(html.extensions.get::<String>(), Some(&"Head".to_string()));
+        assert_eq!(html.extensions.len(), 1);
+
+        let elements: Vec<_> = recorder.elements.iter().map(|e| &e.0[..]).collect();
+        assert_eq!(elements, ["html", "head", "title", "body", "h1", "p", "b"]);
+        assert_eq!(recorder.elements[0].1, Segment::None);
+        assert_eq!(recorder.elements[2].1, Segment::Head);
+        assert_eq!(recorder.elements[6].1, Segment::Body);
+        assert_eq!(recorder.elements[6].2, "html>body>p");
+
+        assert_eq!(
+            recorder.texts,
+            vec![
+                ("html>head>title".to_string(), "T".to_string()),
+                ("html>body>h1".to_string(), "Head".to_string()),
+                ("html>body>p".to_string(), "A ".to_string()),
+                ("html>body>p>b".to_string(), "B".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extensions() {
+        let mut extensions = Extensions::default();
+        assert!(extensions.is_empty());
+        assert_eq!(extensions.insert(1u32), None);
+        assert_eq!(extensions.insert(2u32), Some(Arc::new(1)));
+        extensions.insert("str");
+
+        *extensions.get_mut::<u32>().unwrap() += 1;
+        assert_eq!(extensions.get::<u32>(), Some(&3));
+
+        let clone = extensions.clone();
+        assert!(extensions.get_mut::<u32>().is_none());
+        assert_eq!(clone.get::<&str>(), Some(&"str"));
+
+        assert_eq!(extensions.remove::<u32>(), Some(Arc::new(3)));
+        assert_eq!(extensions.get::<u32>(), None);
+        assert_eq!(extensions.len(), 1);
+    }
+}
@@ -0,0 +1,489 @@
+//! RSS, Atom and JSON feeds
+
+use std::io;
+use std::time::SystemTime;
+
+use markup5ever_rcdom::Handle;
+use serde_json::Value;
+
+use crate::date::{from_iso8601, from_rfc2822};
+use crate::xml;
+#[cfg(feature = "curl")]
+use crate::{Client, Request, Webpage, WebpageOptions};
+
+/// Format of a feed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FeedKind {
+    /// RSS 0.9x and 2.0
+    Rss,
+    /// RSS 1.0 (RDF)
+    Rdf,
+    /// Atom 1.0
+    Atom,
+    /// JSON Feed 1.x
+    Json,
+}
+
+/// Parsed feed
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Feed {
+    /// Format of the feed
+    pub kind: FeedKind,
+    /// Title of the feed
+    pub title: Option<String>,
+    /// URL of the website
+    pub link: Option<String>,
+    /// Description or subtitle
+    pub description: Option<String>,
+    /// Time of the last update
+    pub updated: Option<SystemTime>,
+    /// Items (entries) of the feed, in document order
+    pub items: Vec<FeedItem>,
+}
+
+/// Item (entry) of a feed
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct FeedItem {
+    /// Unique identifier (guid)
+    pub id: Option<String>,
+    pub title: Option<String>,
+    /// URL of the item
+    pub link: Option<String>,
+    /// Publication time
+    pub published: Option<SystemTime>,
+    /// Time of the last update
+    pub updated: Option<SystemTime>,
+    /// Name (or email) of the author
+    pub author: Option<String>,
+    /// Summary or description, may contain HTML
+    pub summary: Option<String>,
+    /// Full content, may contain HTML
+    pub content: Option<String>,
+    /// Attached media files
+    pub enclosures: Vec<Enclosure>,
+}
+
+/// Media file attached to a feed item
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Enclosure {
+    pub url: String,
+    pub mime_type: Option<String>,
+    /// Size in bytes
+    pub length: Option<u64>,
+}
+
+impl Feed {
+    /// Parse a RSS, RDF, Atom or JSON feed
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::{Feed, FeedKind};
+    ///
+    /// let input = r#"<rss version="2.0"><channel><title>News</title>
+    ///   <item><title>Hello</title><link>https://example.org/hello</link></item>
+    /// </channel></rss>"#;
+    /// let feed = Feed::parse(input.as_bytes()).unwrap();
+    /// assert_eq!(feed.kind, FeedKind::Rss);
+    /// assert_eq!(feed.items[0].title, Some("Hello".to_string()));
+    /// ```
+    pub fn parse(input: &[u8]) -> Result<Self, io::Error> {
+        let start = input
+            .strip_prefix("\u{feff}".as_bytes())
+            .unwrap_or(input)
+            .iter()
+            .find(|b| !b.is_ascii_whitespace());
+        if start == Some(&b'{') {
+            let value = serde_json::from_slice(input)?;
+            return Self::from_json(&value)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid JSON feed"));
+        }
+
+        let document = xml::parse(input)?;
+        let root = &document.root;
+        match xml::name(root) {
+            "rss" => xml::child(root, "channel")
+                .map(|channel| Self::from_rss(&channel, &channel, FeedKind::Rss)),
+            "RDF" => xml::child(root, "channel")
+                .map(|channel| Self::from_rss(&channel, root, FeedKind::Rdf)),
+            "feed" => Some(Self::from_atom(root)),
+            _ => None,
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unknown feed format"))
+    }
+
+    /// RSS channel, with the items inside the channel (RSS) or next to it (RDF)
+    fn from_rss(channel: &Handle, items: &Handle, kind: FeedKind) -> Self {
+        let items = xml::children(items)
+            .iter()
+            .filter(|child| xml::name(child) == "item")
+            .map(rss_item)
+            .collect();
+
+        Self {
+            kind,
+            title: xml::child_text(channel, "title"),
+            link: xml::child_text(channel, "link"),
+            description: xml::child_text(channel, "description"),
+            updated: xml::child_text(channel, "lastBuildDate")
+                .or_else(|| xml::child_text(channel, "date"))
+                .and_then(|date| parse_date(&date)),
+            items,
+        }
+    }
+
+    fn from_atom(feed: &Handle) -> Self {
+        let items = xml::children(feed)
+            .iter()
+            .filter(|child| xml::name(child) == "entry")
+            .map(atom_entry)
+            .collect();
+
+        Self {
+            kind: FeedKind::Atom,
+            title: xml::child_text(feed, "title"),
+            link: atom_link(feed, "alternate").map(|link| link.url),
+            description: xml::child_text(feed, "subtitle"),
+            updated: xml::child_text(feed, "updated").and_then(|date| from_iso8601(&date)),
+            items,
+        }
+    }
+
+    /// JSON Feed, see <https://www.jsonfeed.org/version/1.1/>
+    fn from_json(value: &Value) -> Option<Self> {
+        if !value["version"].as_str()?.contains("jsonfeed.org") {
+            return None;
+        }
+        let string = |value: &Value| value.as_str().map(str::to_string);
+        let date = |value: &Value| value.as_str().and_then(from_iso8601);
+
+        let items = value["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|item| FeedItem {
+                id: string(&item["id"]).or_else(|| item["id"].as_u64().map(|id| id.to_string())),
+                title: string(&item["title"]),
+                link: string(&item["url"]),
+                published: date(&item["date_published"]),
+                updated: date(&item["date_modified"]),
+                author: string(&item["authors"][0]["name"])
+                    .or_else(|| string(&item["author"]["name"])),
+                summary: string(&item["summary"]),
+                content: string(&item["content_html"]).or_else(|| string(&item["content_text"])),
+                enclosures: item["attachments"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|attachment| {
+                        Some(Enclosure {
+                            url: string(&attachment["url"])?,
+                            mime_type: string(&attachment["mime_type"]),
+                            length: attachment["size_in_bytes"].as_u64(),
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Some(Self {
+            kind: FeedKind::Json,
+            title: string(&value["title"]),
+            link: string(&value["home_page_url"]),
+            description: string(&value["description"]),
+            updated: None,
+            items,
+        })
+    }
+
+    /// Resolve relative item links against the URL of the feed
+    #[cfg_attr(not(feature = "curl"), allow(dead_code))]
+    fn resolve_links(&mut self, base: &str) {
+        if let Ok(base) = url::Url::parse(base) {
+            let links = self
+                .items
+                .iter_mut()
+                .flat_map(|item| item.link.iter_mut())
+                .chain(self.link.iter_mut());
+            for link in links {
+                if let Ok(url) = base.join(link) {
+                    *link = url.to_string();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Feed {
+    /// Fetch and parse a feed
+    pub fn fetch(url: &str, options: WebpageOptions) -> Result<Self, io::Error> {
+        Client::new(options).fetch_feed(url)
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Client {
+    /// Fetch and parse a feed
+    pub fn fetch_feed(&self, url: &str) -> Result<Feed, io::Error> {
+        let (http, body) = self.send_raw(Request::get(url))?;
+        if !(200..300).contains(&http.response_code) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("HTTP status {} for feed {}", http.response_code, url),
+            ));
+        }
+
+        let mut feed = Feed::parse(&body)?;
+        feed.resolve_links(&http.url);
+        Ok(feed)
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Webpage {
+    /// Fetch and parse the feed linked from this page, if any
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Webpage, WebpageOptions};
+    ///
+    /// let webpage = Webpage::from_url("https://example.org", WebpageOptions::default()).unwrap();
+    /// if let Some(Ok(feed)) = webpage.fetch_feed(WebpageOptions::default()) {
+    ///     for item in feed.items {
+    ///         println!("{:?} {:?}", item.title, item.link);
+    ///     }
+    /// }
+    /// ```
+    pub fn fetch_feed(&self, options: WebpageOptions) -> Option<Result<Feed, io::Error>> {
+        let feed = self.html.feed.as_ref()?;
+        // the feed link may be relative to the page
+        let url = url::Url::parse(&self.http.url)
+            .and_then(|base| base.join(feed))
+            .map_or_else(|_| feed.clone(), String::from);
+        Some(Feed::fetch(&url, options))
+    }
+}
+
+fn rss_item(item: &Handle) -> FeedItem {
+    let enclosures = xml::children(item)
+        .iter()
+        .filter(|child| xml::name(child) == "enclosure")
+        .filter_map(|enclosure| {
+            Some(Enclosure {
+                url: xml::attribute(enclosure, "url")?,
+                mime_type: xml::attribute(enclosure, "type"),
+                length: xml::attribute(enclosure, "length").and_then(|l| l.parse().ok()),
+            })
+        })
+        .collect();
+
+    FeedItem {
+        id: xml::child_text(item, "guid").or_else(|| xml::attribute(item, "about")),
+        title: xml::child_text(item, "title"),
+        link: xml::child_text(item, "link"),
+        // dc:date for RDF
+        published: xml::child_text(item, "pubDate")
+            .or_else(|| xml::child_text(item, "date"))
+            .and_then(|date| parse_date(&date)),
+        updated: None,
+        // dc:creator
+        author: xml::child_text(item, "author").or_else(|| xml::child_text(item, "creator")),
+        summary: xml::child_text(item, "description"),
+        // content:encoded
+        content: xml::child_text(item, "encoded"),
+        enclosures,
+    }
+}
+
+fn atom_entry(entry: &Handle) -> FeedItem {
+    let enclosures = xml::children(entry)
+        .iter()
+        .filter(|child| {
+            xml::name(child) == "link"
+                && xml::attribute(child, "rel").as_deref() == Some("enclosure")
+        })
+        .filter_map(atom_enclosure)
+        .collect();
+
+    let updated = xml::child_text(entry, "updated").and_then(|date| from_iso8601(&date));
+    FeedItem {
+        id: xml::child_text(entry, "id"),
+        title: xml::child_text(entry, "title"),
+        link: atom_link(entry, "alternate").map(|link| link.url),
+        published: xml::child_text(entry, "published")
+            .and_then(|date| from_iso8601(&date))
+            .or(updated),
+        updated,
+        author: xml::child(entry, "author").and_then(|author| {
+            xml::child_text(&author, "name").or_else(|| xml::child_text(&author, "email"))
+        }),
+        summary: xml::child_text(entry, "summary"),
+        content: xml::child_text(entry, "content"),
+        enclosures,
+    }
+}
+
+/// First `<link>` with the given relation, a missing `rel` means alternate
+fn atom_link(handle: &Handle, rel: &str) -> Option<Enclosure> {
+    xml::children(handle)
+        .iter()
+        .filter(|child| xml::name(child) == "link")
+        .find(|link| {
+            xml::attribute(link, "rel")
+                .as_deref()
+                .unwrap_or("alternate")
+                == rel
+        })
+        .and_then(atom_enclosure)
+}
+
+fn atom_enclosure(link: &Handle) -> Option<Enclosure> {
+    Some(Enclosure {
+        url: xml::attribute(link, "href")?,
+        mime_type: xml::attribute(link, "type"),
+        length: xml::attribute(link, "length").and_then(|l| l.parse().ok()),
+    })
+}
+
+/// RSS dates are RFC 822, but ISO 8601 is common as well
+fn parse_date(input: &str) -> Option<SystemTime> {
+    from_rfc2822(input).or_else(|| from_iso8601(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn leap_day() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_709_209_800)
+    }
+
+    #[test]
+    fn test_rss() {
+        let input = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/"
+     xmlns:content="http://purl.org/rss/1.0/modules/content/">
+  <channel>
+    <title>Example</title>
+    <link>https://example.org/</link>
+    <description>News &amp; more</description>
+    <item>
+      <title>Leap day</title>
+      <link>/leap-day</link>
+      <guid isPermaLink="false">42</guid>
+      <pubDate>Thu, 29 Feb 2024 13:30:00 +0100</pubDate>
+      <dc:creator>Otto</dc:creator>
+      <description><![CDATA[<p>Once every <b>four</b> years</p>]]></description>
+      <content:encoded>Full text</content:encoded>
+      <enclosure url="https://example.org/leap.mp3" type="audio/mpeg" length="1234"/>
+    </item>
+  </channel>
+</rss>"#;
+        let mut feed = Feed::parse(input.as_bytes()).unwrap();
+        feed.resolve_links("https://example.org/feed.xml");
+        assert_eq!(feed.kind, FeedKind::Rss);
+        assert_eq!(feed.description, Some("News & more".to_string()));
+
+        let item = &feed.items[0];
+        assert_eq!(item.id, Some("42".to_string()));
+        assert_eq!(item.link, Some("https://example.org/leap-day".to_string()));
+        assert_eq!(item.published, Some(leap_day()));
+        assert_eq!(item.author, Some("Otto".to_string()));
+        assert_eq!(
+            item.summary,
+            Some("<p>Once every <b>four</b> years</p>".to_string())
+        );
+        assert_eq!(item.content, Some("Full text".to_string()));
+        assert_eq!(item.enclosures[0].length, Some(1234));
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        let input = r#"<rss version="2.0"><channel><title>Example</title>
+  <item><title>A</title><pubDate>Thu, 29 Feb 99999999999999999 12:30:00 GMT</pubDate></item>
+  <item><title>B</title><pubDate>2024-02-29T99999999999999999:00Z</pubDate></item>
+  <item><title>C</title><pubDate>2024-02-29T12:30:00.12345678é</pubDate></item>
+</channel></rss>"#;
+        let feed = Feed::parse(input.as_bytes()).unwrap();
+        assert_eq!(feed.items.len(), 3);
+        assert!(feed.items.iter().all(|item| item.published.is_none()));
+    }
+
+    #[test]
+    fn test_rdf() {
+        let input = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+         xmlns="http://purl.org/rss/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel rdf:about="https://example.org/"><title>Example</title></channel>
+  <item rdf:about="https://example.org/a">
+    <title>A</title><link>https://example.org/a</link><dc:date>2024-02-29T12:30:00Z</dc:date>
+  </item>
+</rdf:RDF>"#;
+        let feed = Feed::parse(input.as_bytes()).unwrap();
+        assert_eq!(feed.kind, FeedKind::Rdf);
+        assert_eq!(feed.title, Some("Example".to_string()));
+        assert_eq!(feed.items[0].id, Some("https://example.org/a".to_string()));
+        assert_eq!(feed.items[0].published, Some(leap_day()));
+    }
+
+    #[test]
+    fn test_atom() {
+        let input = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Example</title>
+  <link rel="self" href="https://example.org/atom.xml"/>
+  <link href="https://example.org/"/>
+  <updated>2024-02-29T12:30:00Z</updated>
+  <entry>
+    <id>urn:uuid:1</id>
+    <title type="html">Leap &lt;b&gt;day&lt;/b&gt;</title>
+    <link rel="alternate" type="text/html" href="https://example.org/leap-day"/>
+    <link rel="enclosure" type="video/mp4" href="https://example.org/leap.mp4"/>
+    <updated>2024-02-29T12:30:00Z</updated>
+    <author><name>Otto</name></author>
+    <summary>Summary</summary>
+  </entry>
+</feed>"#;
+        let feed = Feed::parse(input.as_bytes()).unwrap();
+        assert_eq!(feed.kind, FeedKind::Atom);
+        assert_eq!(feed.link, Some("https://example.org/".to_string()));
+        assert_eq!(feed.updated, Some(leap_day()));
+
+        let entry = &feed.items[0];
+        assert_eq!(entry.title, Some("Leap <b>day</b>".to_string()));
+        assert_eq!(entry.link, Some("https://example.org/leap-day".to_string()));
+        assert_eq!(entry.published, Some(leap_day()));
+        assert_eq!(entry.author, Some("Otto".to_string()));
+        assert_eq!(entry.enclosures[0].mime_type, Some("video/mp4".to_string()));
+    }
+
+    #[test]
+    fn test_json() {
+        let input = r#"{
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": "Example",
+            "items": [{
+                "id": "1",
+                "url": "https://example.org/leap-day",
+                "content_text": "Full text",
+                "date_published": "2024-02-29T12:30:00Z",
+                "authors": [{"name": "Otto"}],
+                "attachments": [{"url": "https://example.org/leap.mp3", "mime_type": "audio/mpeg"}]
+            }]
+        }"#;
+        let feed = Feed::parse(input.as_bytes()).unwrap();
+        assert_eq!(feed.kind, FeedKind::Json);
+        assert_eq!(feed.items[0].published, Some(leap_day()));
+        assert_eq!(feed.items[0].author, Some("Otto".to_string()));
+        assert_eq!(feed.items[0].content, Some("Full text".to_string()));
+        assert_eq!(feed.items[0].enclosures.len(), 1);
+
+        assert!(Feed::parse(b"{\"title\": \"not a feed\"}").is_err());
+    }
+}
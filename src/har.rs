@@ -0,0 +1,152 @@
+//! Export of HTTP transfers as [HTTP Archive](http://www.softwareishard.com/blog/har-12-spec/)
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::date::to_iso8601;
+use crate::headers;
+use crate::{Exchange, HTTP};
+
+impl HTTP {
+    /// Export the transfer as a HAR 1.2 document, with one entry per request (including
+    /// redirects)
+    ///
+    /// The entries are taken from `HTTP.exchanges`, so the transfer must have been performed with
+    /// `WebpageOptions.record_transfer` enabled.
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{WebpageOptions, HTTP};
+    ///
+    /// let mut options = WebpageOptions::default();
+    /// options.record_transfer = true;
+    ///
+    /// let http = HTTP::fetch("http://example.org", options).unwrap();
+    /// std::fs::write("example.har", http.to_har().to_string()).unwrap();
+    /// ```
+    pub fn to_har(&self) -> Value {
+        let num_exchanges = self.exchanges.len();
+        let entries: Vec<Value> = self
+            .exchanges
+            .iter()
+            .enumerate()
+            .map(|(i, exchange)| {
+                let mut entry = har_entry(exchange);
+                if i + 1 == num_exchanges && !self.ip.is_empty() {
+                    entry["serverIPAddress"] = json!(self.ip);
+                }
+                entry
+            })
+            .collect();
+
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "pages": [],
+                "entries": entries,
+            }
+        })
+    }
+}
+
+fn har_entry(exchange: &Exchange) -> Value {
+    let request_line = exchange.request_headers.first().map_or("", |l| &l[..]);
+    let status_line = exchange.response_headers.first().map_or("", |l| &l[..]);
+
+    // "GET /path HTTP/1.1" and "HTTP/1.1 200 OK"
+    let request_version = request_line.rsplit(' ').next().unwrap_or_default();
+    let mut status_parts = status_line.splitn(3, ' ');
+    let response_version = status_parts.next().unwrap_or_default();
+    let status: u32 = status_parts
+        .next()
+        .and_then(|code| code.parse().ok())
+        .unwrap_or_default();
+    let status_text = status_parts.next().unwrap_or_default();
+
+    let query_string: Vec<Value> = Url::parse(&exchange.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({"name": name, "value": value}))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut request = json!({
+        "method": exchange.method,
+        "url": exchange.url,
+        "httpVersion": request_version,
+        "cookies": [],
+        "headers": har_headers(&exchange.request_headers),
+        "queryString": query_string,
+        "headersSize": headers_size(&exchange.request_headers),
+        "bodySize": exchange.request_body.len(),
+    });
+    if !exchange.request_body.is_empty() {
+        request["postData"] = json!({
+            "mimeType": headers::value(&exchange.request_headers, "content-type").unwrap_or_default(),
+            "text": String::from_utf8_lossy(&exchange.request_body),
+        });
+    }
+
+    let mut content = json!({
+        "size": exchange.response_body.len(),
+        "mimeType": headers::value(&exchange.response_headers, "content-type").unwrap_or_default(),
+    });
+    match std::str::from_utf8(&exchange.response_body) {
+        Ok(text) => content["text"] = json!(text),
+        Err(_) => {
+            content["text"] = json!(BASE64.encode(&exchange.response_body));
+            content["encoding"] = json!("base64");
+        }
+    }
+
+    let timings = &exchange.timings;
+    let millis = |duration: std::time::Duration| duration.as_secs_f64() * 1000.;
+    let optional_millis = |duration: Option<std::time::Duration>| duration.map_or(-1., millis);
+
+    json!({
+        "startedDateTime": to_iso8601(exchange.started),
+        "time": millis(timings.total()),
+        "request": request,
+        "response": {
+            "status": status,
+            "statusText": status_text,
+            "httpVersion": response_version,
+            "cookies": [],
+            "headers": har_headers(&exchange.response_headers),
+            "content": content,
+            "redirectURL": headers::value(&exchange.response_headers, "location").unwrap_or_default(),
+            "headersSize": headers_size(&exchange.response_headers),
+            "bodySize": exchange.response_body.len(),
+        },
+        "cache": {},
+        "timings": {
+            "blocked": -1,
+            "dns": optional_millis(timings.dns),
+            "connect": millis(timings.connect),
+            "ssl": optional_millis(timings.ssl),
+            "send": millis(timings.send),
+            "wait": millis(timings.wait),
+            "receive": millis(timings.receive),
+        },
+    })
+}
+
+fn har_headers(lines: &[String]) -> Vec<Value> {
+    lines
+        .iter()
+        .filter_map(|line| headers::split(line))
+        .map(|(name, value)| json!({"name": name, "value": value}))
+        .collect()
+}
+
+/// Size of the header block on the wire, with CRLF line endings and the terminating empty line
+fn headers_size(lines: &[String]) -> usize {
+    lines.iter().map(|line| line.len() + 2).sum::<usize>() + 2
+}
@@ -0,0 +1,48 @@
+//! Helpers for raw HTTP header lines
+
+/// Split a `Name: value` header line, returns `None` for status lines
+pub(crate) fn split(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with("HTTP/") {
+        return None;
+    }
+    line.split_once(':')
+        .map(|(name, value)| (name.trim(), value.trim()))
+}
+
+/// All values of the headers with the given name (case insensitive)
+pub(crate) fn values<'a: 'b, 'b>(
+    headers: &'a [String],
+    name: &'b str,
+) -> impl Iterator<Item = &'a str> + 'b {
+    headers
+        .iter()
+        .filter_map(|line| split(line))
+        .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// Value of the first header with the given name (case insensitive)
+pub(crate) fn value<'a>(headers: &'a [String], name: &str) -> Option<&'a str> {
+    values(headers, name).next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values() {
+        let headers = vec![
+            "HTTP/1.1 200 OK".to_string(),
+            "Content-Type: text/html".to_string(),
+            "vary: Accept".to_string(),
+            "Vary: Cookie".to_string(),
+        ];
+        assert_eq!(value(&headers, "content-type"), Some("text/html"));
+        assert_eq!(
+            values(&headers, "Vary").collect::<Vec<_>>(),
+            ["Accept", "Cookie"]
+        );
+        assert_eq!(value(&headers, "Location"), None);
+    }
+}
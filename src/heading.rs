@@ -0,0 +1,213 @@
+//! Document headings and their outline
+
+use url::Url;
+
+/// Heading from an `<h1>` to `<h6>` element
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Heading {
+    /// Level, from 1 for \<h1\> to 6 for \<h6\>
+    pub level: u8,
+    /// Text content, whitespace collapsed
+    pub text: String,
+    /// Anchor from the `id` attribute
+    pub id: Option<String>,
+    /// URL of the document with the anchor as fragment, when both are known
+    pub url: Option<String>,
+}
+
+impl Heading {
+    pub(crate) fn new(level: u8, text: String, id: Option<String>, base: Option<&Url>) -> Self {
+        let id = id.filter(|id| !id.is_empty());
+        let url = match (base, &id) {
+            (Some(base), Some(id)) => {
+                let mut url = base.clone();
+                url.set_fragment(Some(id));
+                Some(url.to_string())
+            }
+            _ => None,
+        };
+
+        Self {
+            level,
+            text,
+            id,
+            url,
+        }
+    }
+}
+
+/// Level of a heading element, `h1` to `h6`
+pub(crate) fn level(tag_name: &str) -> Option<u8> {
+    match tag_name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+        _ => None,
+    }
+}
+
+/// Heading in the document outline, with the headings of its subsections
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct OutlineEntry {
+    pub heading: Heading,
+    /// Following headings of a lower level, up to the next heading of the same or a higher level
+    pub children: Vec<OutlineEntry>,
+}
+
+/// Problem in the heading structure of a document
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum HeadingIssue {
+    /// The document has no \<h1\>
+    MissingH1,
+    /// The document has more than one \<h1\>, with the number found
+    MultipleH1(usize),
+    /// A heading is more than one level below the previous heading, e.g. an \<h4\> after an \<h2\>
+    SkippedLevel {
+        /// Index of the heading in `HTML.headings`
+        index: usize,
+        /// Level of the previous heading
+        from: u8,
+        /// Level of this heading
+        to: u8,
+    },
+}
+
+/// Nest the headings by level
+pub(crate) fn outline(headings: &[Heading]) -> Vec<OutlineEntry> {
+    let mut roots = Vec::new();
+    // the path from the root to the last heading, each open to more children
+    let mut open: Vec<OutlineEntry> = Vec::new();
+
+    for heading in headings {
+        while open
+            .last()
+            .map_or(false, |entry| entry.heading.level >= heading.level)
+        {
+            close(&mut open, &mut roots);
+        }
+        open.push(OutlineEntry {
+            heading: heading.clone(),
+            children: Vec::new(),
+        });
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+
+    roots
+}
+
+/// Add the innermost open entry to its parent
+fn close(open: &mut Vec<OutlineEntry>, roots: &mut Vec<OutlineEntry>) {
+    if let Some(entry) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(entry),
+            None => roots.push(entry),
+        }
+    }
+}
+
+/// Check the headings for a single \<h1\> and for skipped levels
+pub(crate) fn check(headings: &[Heading]) -> Vec<HeadingIssue> {
+    let mut issues = Vec::new();
+
+    match headings.iter().filter(|heading| heading.level == 1).count() {
+        0 => issues.push(HeadingIssue::MissingH1),
+        1 => (),
+        count => issues.push(HeadingIssue::MultipleH1(count)),
+    }
+
+    for (index, pair) in headings.windows(2).enumerate() {
+        let (from, to) = (pair[0].level, pair[1].level);
+        if to > from + 1 {
+            issues.push(HeadingIssue::SkippedLevel {
+                index: index + 1,
+                from,
+                to,
+            });
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heading(level: u8, text: &str) -> Heading {
+        Heading {
+            level,
+            text: text.to_string(),
+            id: None,
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let base = Url::parse("https://example.org/page?q=1#top").unwrap();
+        let heading = Heading::new(2, "Intro".into(), Some("intro".into()), Some(&base));
+        assert_eq!(
+            heading.url,
+            Some("https://example.org/page?q=1#intro".to_string())
+        );
+
+        let heading = Heading::new(2, "Intro".into(), Some(String::new()), Some(&base));
+        assert_eq!(heading.id, None);
+        assert_eq!(heading.url, None);
+
+        assert_eq!(level("h6"), Some(6));
+        assert_eq!(level("h7"), None);
+        assert_eq!(level("header"), None);
+    }
+
+    #[test]
+    fn test_outline() {
+        let headings = [
+            heading(2, "Preface"),
+            heading(1, "Title"),
+            heading(2, "A"),
+            heading(4, "A.1"),
+            heading(3, "A.2"),
+            heading(2, "B"),
+        ];
+        let outline = outline(&headings);
+        assert_eq!(outline.len(), 2);
+        assert_eq!(outline[0].heading.text, "Preface");
+        assert!(outline[0].children.is_empty());
+
+        let title = &outline[1];
+        assert_eq!(title.children.len(), 2);
+        let a = &title.children[0];
+        let texts: Vec<_> = a
+            .children
+            .iter()
+            .map(|entry| &entry.heading.text[..])
+            .collect();
+        assert_eq!(texts, vec!["A.1", "A.2"]);
+        assert_eq!(title.children[1].heading.text, "B");
+
+        assert_eq!(
+            check(&headings),
+            vec![HeadingIssue::SkippedLevel {
+                index: 3,
+                from: 2,
+                to: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check(&[]), vec![HeadingIssue::MissingH1]);
+        assert_eq!(
+            check(&[heading(1, "A"), heading(1, "B"), heading(2, "C")]),
+            vec![HeadingIssue::MultipleH1(2)]
+        );
+    }
+}
@@ -1,19 +1,24 @@
 //! Info from the parsed HTML document
 
-use html5ever::driver::ParseOpts;
-use html5ever::parse_document;
-use html5ever::tendril::TendrilSink;
-use markup5ever_rcdom::RcDom;
 use url::Url;
 
 use std::collections::HashMap;
 use std::default::Default;
+use std::fs;
 use std::io;
-use std::path::Path;
 
+use crate::extractor::{Extensions, Extractor};
+use crate::heading::{self, Heading, HeadingIssue, OutlineEntry};
+use crate::icon::{self, Icon};
+use crate::image::Image;
+use crate::language::{self, Language};
+use crate::oembed::OEmbedEndpoint;
 use crate::opengraph::Opengraph;
-use crate::parser::Parser;
+use crate::parser;
+use crate::robots::Robots;
 use crate::schema_org::SchemaOrg;
+use crate::sink::Document;
+use crate::twitter_card::TwitterCard;
 
 /// Information regarding the HTML content
 #[derive(Debug, Clone)]
@@ -28,22 +33,48 @@ pub struct HTML {
     pub url: Option<String>,
     #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) url_parsed: Option<Url>,
+    /// URL from \<base href\>, relative URLs in the document resolve against it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) base_url: Option<Url>,
     /// Feed URL (atom, rss, ..)
     pub feed: Option<String>,
+    /// URL of the next page in a series
+    pub next: Option<String>,
+    /// URL of the previous page in a series
+    pub prev: Option<String>,
+    /// oEmbed endpoints (JSON and XML)
+    pub oembed: Vec<OEmbedEndpoint>,
+    /// Favicons and touch icons, including the `/favicon.ico` fallback
+    pub icons: Vec<Icon>,
+    /// Web app manifest URL
+    pub manifest: Option<String>,
 
     /// Language as specified in the document
     pub language: Option<String>,
-    /// Text content inside \<body\>, all tags stripped
+    /// Text content inside \<body\>, all tags stripped; blocks start on a new line and
+    /// paragraphs are separated by a blank line
     pub text_content: String,
 
     /// Flattened down list of meta properties
     pub meta: HashMap<String, String>,
     /// Opengraph tags
     pub opengraph: Opengraph,
+    /// Twitter Card tags
+    pub twitter_card: TwitterCard,
     /// Schema.org data
     pub schema_org: Vec<SchemaOrg>,
+    /// Robots directives from \<meta name="robots"\> and crawler specific meta tags
+    pub robots: Robots,
     /// All links in the document
     pub links: Vec<Link>,
+    /// Headings \<h1\> to \<h6\> inside \<body\>, in document order
+    pub headings: Vec<Heading>,
+    /// Images from \<img\>, \<picture\> sources and image inputs
+    pub images: Vec<Image>,
+
+    /// Custom data, stored by an [`Extractor`] or by the user
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub extensions: Extensions,
 }
 
 impl HTML {
@@ -54,33 +85,69 @@ impl HTML {
             description: None,
             url,
             url_parsed,
+            base_url: None,
             feed: None,
+            next: None,
+            prev: None,
+            oembed: Vec::new(),
+            icons: Vec::new(),
+            manifest: None,
 
             language: None,
             text_content: String::new(),
 
             meta: HashMap::new(),
             opengraph: Opengraph::empty(),
+            twitter_card: TwitterCard::default(),
             schema_org: Vec::new(),
+            robots: Robots::default(),
             links: Vec::new(),
+            headings: Vec::new(),
+            images: Vec::new(),
+
+            extensions: Extensions::default(),
         }
     }
 
-    /// Construct HTML from RcDom, optionally with a URL set
-    fn from_dom(dom: RcDom, url: Option<String>) -> Self {
+    /// Construct HTML from a parsed document, optionally with a URL set
+    pub(crate) fn from_document(
+        document: &Document,
+        url: Option<String>,
+        extractors: &mut [&mut dyn Extractor],
+    ) -> Self {
         let mut html = Self::empty(url);
-        let parser = Parser::start(dom.document);
-        parser.traverse(&mut html);
+        parser::extract(document, &mut html, extractors);
+
+        // browsers request /favicon.ico when the page does not link an icon
+        let has_favicon = html.icons.iter().any(|icon| icon.rel == "icon");
+        if !has_favicon {
+            let favicon = html.url_parsed.as_ref().and_then(Icon::favicon);
+            html.icons.extend(favicon);
+        }
 
         html
     }
 
+    /// Construct HTML from a DOM built by `markup5ever_rcdom`, to compare against in tests
+    #[cfg(test)]
+    pub(crate) fn from_dom(dom: markup5ever_rcdom::RcDom, url: Option<String>) -> Self {
+        Self::from_document(&Document::from_rcdom(&dom), url, &mut [])
+    }
+
     /// Construct HTML from File, optionally with a URL set
     pub fn from_file(path: &str, url: Option<String>) -> Result<Self, io::Error> {
-        parse_document(RcDom::default(), ParseOpts::default())
-            .from_utf8()
-            .from_file(Path::new(path))
-            .map(|dom| Self::from_dom(dom, url))
+        Self::from_file_with(path, url, &mut [])
+    }
+
+    /// Construct HTML from File, optionally with a URL set, running custom extractors
+    pub fn from_file_with(
+        path: &str,
+        url: Option<String>,
+        extractors: &mut [&mut dyn Extractor],
+    ) -> Result<Self, io::Error> {
+        let bytes = fs::read(path)?;
+        let html = String::from_utf8_lossy(&bytes).into_owned();
+        Self::from_string_with(html, url, extractors)
     }
 
     /// Construct HTML from String, optionally with a URL set
@@ -94,10 +161,103 @@ impl HTML {
     /// assert!(html.is_ok());
     ///  ```
     pub fn from_string(html: String, url: Option<String>) -> Result<Self, io::Error> {
-        parse_document(RcDom::default(), ParseOpts::default())
-            .from_utf8()
-            .read_from(&mut html.as_bytes())
-            .map(|dom| Self::from_dom(dom, url))
+        Self::from_string_with(html, url, &mut [])
+    }
+
+    /// Construct HTML from String, optionally with a URL set, running custom extractors
+    ///
+    /// See [`Extractor`] for an example.
+    pub fn from_string_with(
+        html: String,
+        url: Option<String>,
+        extractors: &mut [&mut dyn Extractor],
+    ) -> Result<Self, io::Error> {
+        Ok(Self::from_document(
+            &Document::parse(&html),
+            url,
+            extractors,
+        ))
+    }
+
+    /// Icon best suited for display at the given size in pixels
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::HTML;
+    ///
+    /// let input = String::from(r#"<link rel="icon" href="/small.png" sizes="16x16">
+    ///     <link rel="apple-touch-icon" href="/touch.png" sizes="180x180">"#);
+    /// let html = HTML::from_string(input, Some("https://example.org/".to_string())).unwrap();
+    /// let icon = html.best_icon(64).unwrap();
+    /// assert_eq!(icon.url, "https://example.org/touch.png");
+    /// ```
+    pub fn best_icon(&self, size: u32) -> Option<&Icon> {
+        icon::best(&self.icons, size)
+    }
+
+    /// Resolve the language from the `lang` attribute, the content-language meta tag, the
+    /// given `Content-Language` header value, or else detect it from the text
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::{HTML, LanguageSource};
+    ///
+    /// let input = String::from("<html lang='en_gb'><body>Hello");
+    /// let html = HTML::from_string(input, None).unwrap();
+    /// let language = html.resolve_language(None).unwrap();
+    /// assert_eq!(language.tag, "en-GB");
+    /// assert_eq!(language.source, LanguageSource::Attribute);
+    /// ```
+    pub fn resolve_language(&self, content_language: Option<&str>) -> Option<Language> {
+        let meta = self
+            .meta
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-language"))
+            .map(|(_, content)| &content[..]);
+
+        language::resolve(
+            self.language.as_deref(),
+            meta,
+            content_language,
+            &self.text_content,
+        )
+    }
+
+    /// Headings nested by level, each with the headings of its subsections
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::HTML;
+    ///
+    /// let input = String::from("<h1>Title</h1><h2>First</h2><h3>Detail</h3><h2>Second</h2>");
+    /// let html = HTML::from_string(input, None).unwrap();
+    /// let outline = html.outline();
+    /// assert_eq!(outline.len(), 1);
+    /// assert_eq!(outline[0].children.len(), 2);
+    /// assert_eq!(outline[0].children[0].children[0].heading.text, "Detail");
+    /// ```
+    pub fn outline(&self) -> Vec<OutlineEntry> {
+        heading::outline(&self.headings)
+    }
+
+    /// Problems in the heading structure: a missing or repeated \<h1\>, and skipped levels
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::{HeadingIssue, HTML};
+    ///
+    /// let input = String::from("<h2>Intro</h2><h4>Detail</h4>");
+    /// let html = HTML::from_string(input, None).unwrap();
+    /// assert_eq!(
+    ///     html.heading_issues(),
+    ///     vec![
+    ///         HeadingIssue::MissingH1,
+    ///         HeadingIssue::SkippedLevel { index: 1, from: 2, to: 4 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn heading_issues(&self) -> Vec<HeadingIssue> {
+        heading::check(&self.headings)
     }
 
     pub(crate) fn set_url(&mut self, url: Option<String>) {
@@ -118,6 +278,11 @@ pub struct Link {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::image::ImageElement;
+    use html5ever::driver::ParseOpts;
+    use html5ever::parse_document;
+    use html5ever::tendril::TendrilSink;
+    use markup5ever_rcdom::RcDom;
 
     #[test]
     fn from_string() {
@@ -129,7 +294,7 @@ mod tests {
         let html = html.unwrap();
         assert_eq!(html.title, Some("Hello".to_string()));
         assert!(html.description.is_none());
-        assert_eq!(html.text_content, "Contents  Link".to_string());
+        assert_eq!(html.text_content, "Contents Link".to_string());
         assert_eq!(
             html.links,
             vec![Link {
@@ -138,4 +303,188 @@ mod tests {
             }]
         );
     }
+    #[test]
+    fn text_content() {
+        let input = "<body><h1>Title</h1><p>First&nbsp;line<br>second\n  line</p>
+            <ul><li>One</li><li>Two &amp; <b>three</b></li></ul><pre>  keep\n    this</pre>
+            <script>ignored()</script><div>Last</div>"
+            .to_string();
+        let html = HTML::from_string(input, None).unwrap();
+        assert_eq!(
+            html.text_content,
+            "Title\n\nFirst\u{a0}line\nsecond line\n\nOne\nTwo & three\n\n  keep\n    this\n\nLast"
+        );
+    }
+
+    #[test]
+    fn headings() {
+        let input = r##"<link rel="canonical" href="https://example.com/post">
+            <h1 id="top">The <em>full</em>
+                title</h1><h2><a href="#a">1.</a> Section<script>x()</script></h2>"##;
+        let url = Some("https://example.com/post?utm_source=feed".to_string());
+        let html = HTML::from_string(input.to_string(), url).unwrap();
+        assert_eq!(html.headings.len(), 2);
+        assert_eq!(html.headings[0].text, "The full title");
+        assert_eq!(html.headings[0].id, Some("top".to_string()));
+        assert_eq!(
+            html.headings[0].url,
+            Some("https://example.com/post#top".to_string())
+        );
+        assert_eq!(html.headings[1].level, 2);
+        assert_eq!(html.headings[1].text, "1. Section");
+        assert_eq!(html.headings[1].url, None);
+    }
+
+    #[test]
+    fn images() {
+        let input = r#"<head><base href="/static/"></head><body>
+            <picture><source srcset="a.webp 1x, a@2x.webp 2x" type="image/webp"
+                media="(min-width: 800px)"><img src="a.jpg" alt="" width="640px" height=480></picture>
+            <img src="data:image/gif;base64,R0lGOD" data-src="lazy.jpg" data-srcset="lazy.jpg 320w"
+                sizes="(max-width: 600px) 100vw, 50vw" loading="LAZY" title="Lazy">
+            <source srcset="ignored.jpg"><input type="IMAGE" src="https://cdn.test/go.png" alt="Go">"#;
+        let url = Some("https://example.com/blog/post".to_string());
+        let html = HTML::from_string(input.to_string(), url).unwrap();
+        assert_eq!(html.images.len(), 4);
+
+        let source = &html.images[0];
+        assert_eq!(source.element, ImageElement::Source);
+        assert_eq!(source.src, None);
+        assert_eq!(source.srcset[1].url, "https://example.com/static/a@2x.webp");
+        assert_eq!(source.srcset[1].density, Some(2.));
+        assert_eq!(source.mime_type, Some("image/webp".to_string()));
+        assert_eq!(source.media, Some("(min-width: 800px)".to_string()));
+
+        let img = &html.images[1];
+        assert_eq!(
+            img.src,
+            Some("https://example.com/static/a.jpg".to_string())
+        );
+        assert_eq!(img.alt, Some(String::new()));
+        assert_eq!((img.width, img.height), (Some(640), Some(480)));
+
+        let lazy = &html.images[2];
+        assert_eq!(lazy.url(), Some("https://example.com/static/lazy.jpg"));
+        assert_eq!(lazy.lazy_srcset[0].width, Some(320));
+        assert_eq!(lazy.sizes.len(), 2);
+        assert_eq!(lazy.loading, Some("lazy".to_string()));
+        assert_eq!(lazy.title, Some("Lazy".to_string()));
+
+        let input = &html.images[3];
+        assert_eq!(input.element, ImageElement::Input);
+        assert_eq!(input.url(), Some("https://cdn.test/go.png"));
+    }
+
+    #[test]
+    fn base_href() {
+        let input = r#"<head><base href="/docs/"><base href="/ignored/">
+            <link rel="icon" href="icon.png"></head>
+            <body><a href="intro">Intro</a><a href="/about">About</a>"#;
+        let url = Some("https://example.com/blog/post".to_string());
+        let html = HTML::from_string(input.to_string(), url).unwrap();
+        assert_eq!(html.url, Some("https://example.com/blog/post".to_string()));
+        assert_eq!(html.icons[0].url, "https://example.com/docs/icon.png");
+        let urls: Vec<_> = html.links.iter().map(|link| &link.url[..]).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/docs/intro",
+                "https://example.com/about"
+            ]
+        );
+    }
+
+    #[test]
+    fn icons() {
+        let input = r##"<link rel="shortcut icon" href="favicon.png">
+            <link rel="mask-icon" href="/mask.svg" color="#000">"##;
+        let url = Some("https://example.com/blog/".to_string());
+        let html = HTML::from_string(input.to_string(), url.clone()).unwrap();
+        assert_eq!(html.icons.len(), 2);
+        assert_eq!(html.icons[0].url, "https://example.com/blog/favicon.png");
+        assert_eq!(html.icons[1].color, Some("#000".to_string()));
+        assert!(html.icons[1].scalable);
+
+        let html = HTML::from_string("<title>No icons</title>".to_string(), url.clone()).unwrap();
+        assert_eq!(html.icons.len(), 1);
+        assert_eq!(html.icons[0].url, "https://example.com/favicon.ico");
+
+        let input = r#"<link rel="alternate icon" type="image/svg+xml" href="/a.svg">
+            <link rel="Shortcut Icon" href="/b.ico"><link rel=" shortcut  icon " href="/c.ico">"#;
+        let html = HTML::from_string(input.to_string(), url).unwrap();
+        let urls: Vec<_> = html.icons.iter().map(|icon| &icon.url[..]).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/a.svg",
+                "https://example.com/b.ico",
+                "https://example.com/c.ico"
+            ]
+        );
+        assert!(html.icons.iter().all(|icon| icon.rel == "icon"));
+    }
+
+    #[test]
+    fn link_relations() {
+        let input = r#"<link rel="Canonical" href="https://example.com/post">
+            <link rel="alternate nofollow" type="application/rss+xml" href="/feed.xml">
+            <link rel="next nofollow" href="?page=3"><link rel="PREV" href="/post">"#;
+        let url = Some("https://example.com/post?page=2".to_string());
+        let html = HTML::from_string(input.to_string(), url).unwrap();
+        assert_eq!(html.url, Some("https://example.com/post".to_string()));
+        assert_eq!(html.feed, Some("/feed.xml".to_string()));
+        assert_eq!(
+            html.next,
+            Some("https://example.com/post?page=3".to_string())
+        );
+        assert_eq!(html.prev, Some("https://example.com/post".to_string()));
+    }
+
+    #[test]
+    fn same_as_rcdom() {
+        // misnested formatting, foster parented table text and template contents exercise the
+        // less common tree builder operations
+        let input = r#"<!DOCTYPE html><html lang="en"><head><title>Tree builder</title>
+            <meta name="description" content="Test"><meta property="og:title" content="OG">
+            <meta name="twitter:card" content="summary"><meta name="twitter:app:id:ipad" content="1">
+            <script type="application/ld+json">{"@type": "Thing"}</script></head>
+            <body><!-- comment --><p>One <b>two <i>three</b> four</i> five</p>
+            <table>foster<tr><td>cell</td></tr> parented</table>
+            <template><a href="/hidden">Hidden</a></template>
+            <a href="/a">A <span>B</span></a><noscript>No</noscript>text"#;
+        let url = Some("https://example.com/".to_string());
+
+        let html = HTML::from_string(input.to_string(), url.clone()).unwrap();
+        let dom = parse_document(RcDom::default(), ParseOpts::default())
+            .from_utf8()
+            .read_from(&mut input.as_bytes())
+            .unwrap();
+        let reference = HTML::from_dom(dom, url);
+
+        assert_eq!(html.title, Some("Tree builder".to_string()));
+        assert_eq!(html.title, reference.title);
+        assert_eq!(html.description, reference.description);
+        assert_eq!(html.language, reference.language);
+        assert_eq!(html.text_content, reference.text_content);
+        assert_eq!(html.meta, reference.meta);
+        assert_eq!(html.opengraph.properties, reference.opengraph.properties);
+        assert_eq!(html.twitter_card.card, Some("summary".to_string()));
+        assert_eq!(html.twitter_card, reference.twitter_card);
+        assert_eq!(html.schema_org.len(), 1);
+        assert_eq!(
+            format!("{:?}", html.schema_org),
+            format!("{:?}", reference.schema_org)
+        );
+        assert_eq!(html.links, reference.links);
+        assert_eq!(html.icons, reference.icons);
+    }
+
+    #[test]
+    fn deeply_nested() {
+        let depth = 50_000;
+        let input = format!("{}<a href='/deep'>Deep</a>", "<span>".repeat(depth));
+        let html = HTML::from_string(input, None).unwrap();
+        assert_eq!(html.links.len(), 1);
+        assert_eq!(html.text_content, "Deep");
+    }
 }
@@ -1,11 +1,23 @@
 //! Info about the HTTP transfer
 
+use std::fs;
 use std::io;
-use std::time::Duration;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
 
-use curl::easy::{Easy, List};
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use curl::easy::{Easy, InfoType, List};
+use percent_encoding::percent_decode_str;
+use url::Url;
 
-use crate::WebpageOptions;
+use crate::request::{Method, Request};
+use crate::{cache, headers};
+use crate::{
+    CacheControl, Client, LinkRelation, PemSource, Robots, SecurityReport, WebpageOptions,
+};
 
 /// Information regarding the HTTP transfer
 #[derive(Debug, Clone)]
@@ -26,13 +38,73 @@ pub struct HTTP {
     pub headers: Vec<String>,
     /// Effective URL that was visited
     pub url: String,
-    /// HTTP body
+    /// HTTP body, invalid UTF-8 replaced by U+FFFD
     pub body: String,
+    /// Body as received, only kept when it is not valid UTF-8
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) raw_body: Option<Vec<u8>>,
+    /// Time at which the request was started
+    pub request_time: SystemTime,
+    /// Every request and response of the transfer, including redirects (only filled when
+    /// `WebpageOptions.record_transfer` is set)
+    pub exchanges: Vec<Exchange>,
+}
+
+/// A single request/response pair of a HTTP transfer
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Exchange {
+    /// Time at which this request was started
+    pub started: SystemTime,
+    /// Request method
+    pub method: String,
+    /// URL that was requested
+    pub url: String,
+    /// Request line and headers as sent
+    pub request_headers: Vec<String>,
+    /// Request body as sent
+    pub request_body: Vec<u8>,
+    /// Status line and headers as received
+    pub response_headers: Vec<String>,
+    /// Response body as received
+    pub response_body: Vec<u8>,
+    /// Time spent in the phases of this exchange
+    pub timings: Timings,
+}
+
+/// Time spent in the phases of a request/response exchange
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Timings {
+    /// DNS resolution, when measured separately from `connect`
+    pub dns: Option<Duration>,
+    /// Setting up the connection (including TLS) until the request could be sent
+    pub connect: Duration,
+    /// TLS handshake, when measured (part of `connect`)
+    pub ssl: Option<Duration>,
+    /// Sending the request
+    pub send: Duration,
+    /// Waiting for the first byte of the response
+    pub wait: Duration,
+    /// Receiving the response
+    pub receive: Duration,
+}
+
+impl Timings {
+    /// Total time of the exchange
+    pub fn total(&self) -> Duration {
+        self.dns.unwrap_or_default() + self.connect + self.send + self.wait + self.receive
+    }
 }
 
 impl HTTP {
     /// Fetch a webpage from the given URL
     ///
+    /// Besides HTTP(S), `file:` and `data:` URLs are supported: these produce a synthetic response
+    /// with the appropriate content type.
+    ///
     /// ## Examples
     /// ```
     /// use webpage::HTTP;
@@ -45,28 +117,91 @@ impl HTTP {
     /// assert!(info.is_err());
     /// ```
     pub fn fetch(url: &str, options: WebpageOptions) -> Result<Self, io::Error> {
-        let mut handle = Easy::new();
+        Client::new(options).send(Request::get(url))
+    }
+
+    /// Perform the request on the given (freshly reset) curl handle, returns the raw body bytes
+    /// alongside
+    pub(crate) fn perform(
+        handle: &mut Easy,
+        request: &Request,
+        options: &WebpageOptions,
+    ) -> Result<(Self, Vec<u8>), io::Error> {
+        if let Ok(url) = Url::parse(&request.url) {
+            match url.scheme() {
+                "file" => return Self::from_file_url(url, request.method),
+                "data" => return Self::from_data_url(url, request.method),
+                _ => (),
+            }
+        }
+
+        let request_time = SystemTime::now();
 
         // configure
         handle.ssl_verify_peer(!options.allow_insecure)?;
         handle.ssl_verify_host(!options.allow_insecure)?;
+        match options.ca_certificates {
+            Some(PemSource::Path(ref path)) => handle.cainfo(path)?,
+            Some(PemSource::Bytes(ref pem)) => handle.ssl_cainfo_blob(pem)?,
+            None => (),
+        }
+        match options.client_certificate {
+            Some(PemSource::Path(ref path)) => handle.ssl_cert(path)?,
+            Some(PemSource::Bytes(ref pem)) => handle.ssl_cert_blob(pem)?,
+            None => (),
+        }
+        match options.client_key {
+            Some(PemSource::Path(ref path)) => handle.ssl_key(path)?,
+            Some(PemSource::Bytes(ref pem)) => handle.ssl_key_blob(pem)?,
+            None => (),
+        }
+        if let Some(ref password) = options.client_key_password {
+            handle.key_password(password)?;
+        }
+        if !options.pinned_public_keys.is_empty() {
+            handle.pinned_public_key(&options.pinned_public_keys.join(";"))?;
+        }
         handle.timeout(options.timeout)?;
+        if let Some(connect_timeout) = options.connect_timeout {
+            handle.connect_timeout(connect_timeout)?;
+        }
+        if let Some((bytes_per_second, duration)) = options.low_speed_limit {
+            handle.low_speed_limit(bytes_per_second)?;
+            handle.low_speed_time(duration)?;
+        }
+        if let Some(max_recv_speed) = options.max_recv_speed {
+            handle.max_recv_speed(max_recv_speed)?;
+        }
         handle.follow_location(options.follow_location)?;
         handle.max_redirections(options.max_redirections)?;
         handle.useragent(&options.useragent)?;
-        if !options.headers.is_empty() {
+        if !options.headers.is_empty() || !request.headers.is_empty() {
             let mut list = List::new();
-            for header in options.headers.iter() {
+            for header in options.headers.iter().chain(request.headers.iter()) {
                 list.append(header)?;
             }
             handle.http_headers(list)?;
         }
 
-        handle.url(url)?;
+        match request.method {
+            Method::Get => handle.get(true)?,
+            Method::Head => handle.nobody(true)?,
+            Method::Post => {
+                handle.post(true)?;
+                handle.post_fields_copy(request.body.as_deref().unwrap_or_default())?;
+            }
+        }
+
+        handle.url(&request.url)?;
+        if options.record_transfer {
+            // debug callbacks are only invoked in verbose mode
+            handle.verbose(true)?;
+        }
 
         let mut headers = Vec::new();
         let mut body = Vec::new();
-        {
+        let mut recorder = Recorder::new(request_time);
+        let result = {
             let mut transfer = handle.transfer();
             transfer.header_function(|new_data| {
                 let header = String::from_utf8_lossy(new_data)
@@ -91,12 +226,24 @@ impl HTTP {
                 Ok(new_data.len())
             })?;
 
-            transfer.perform()?;
+            if options.record_transfer {
+                transfer.debug_function(|info, data| recorder.record(info, data))?;
+            }
+
+            transfer.perform()
+        };
+        if let Err(e) = result {
+            if e.is_operation_timedout() {
+                let limit = Timeout::from_error(&e, handle, options);
+                return Err(io::Error::new(io::ErrorKind::TimedOut, limit));
+            }
+            return Err(e.into());
         }
 
-        let body = String::from_utf8_lossy(&body).into_owned();
+        let exchanges = recorder.finish(&request.url, &body, handle)?;
+        let (text, raw_body) = decode_body(&body);
 
-        Ok(HTTP {
+        let http = HTTP {
             ip: handle.primary_ip()?.unwrap_or("").to_string(),
             transfer_time: handle.total_time()?,
             redirect_count: handle.redirect_count()?,
@@ -105,7 +252,493 @@ impl HTTP {
             url: handle.effective_url()?.unwrap_or("").to_string(),
 
             headers,
+            body: text,
+            raw_body,
+            request_time,
+            exchanges,
+        };
+        Ok((http, body))
+    }
+
+    /// Read a local file, with the content type guessed from its extension
+    fn from_file_url(url: Url, method: Method) -> Result<(Self, Vec<u8>), io::Error> {
+        let request_time = SystemTime::now();
+        let path = url
+            .to_file_path()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid file URL"))?;
+        let body = fs::read(&path)?;
+
+        Ok(Self::synthetic(
+            url.to_string(),
+            content_type_for(&path),
             body,
-        })
+            method,
+            request_time,
+        ))
+    }
+
+    /// Decode a `data:` URL, see <https://fetch.spec.whatwg.org/#data-urls>
+    fn from_data_url(mut url: Url, method: Method) -> Result<(Self, Vec<u8>), io::Error> {
+        let request_time = SystemTime::now();
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "invalid data URL");
+
+        url.set_fragment(None);
+        let (media_type, data) = url.as_str()["data:".len()..]
+            .split_once(',')
+            .ok_or_else(invalid)?;
+        let mut media_type = media_type.trim();
+        let data: Vec<u8> = percent_decode_str(data).collect();
+
+        let body = match media_type.rsplit_once(';') {
+            Some((rest, base64)) if base64.trim().eq_ignore_ascii_case("base64") => {
+                media_type = rest.trim();
+                let data: Vec<u8> = data
+                    .into_iter()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .collect();
+                let engine = GeneralPurpose::new(
+                    &alphabet::STANDARD,
+                    GeneralPurposeConfig::new()
+                        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+                );
+                engine.decode(data).map_err(|_| invalid())?
+            }
+            _ => data,
+        };
+        let content_type = if media_type.is_empty() || media_type.starts_with(';') {
+            format!("text/plain;charset=US-ASCII{}", media_type)
+        } else {
+            media_type.to_string()
+        };
+
+        Ok(Self::synthetic(
+            url.to_string(),
+            &content_type,
+            body,
+            method,
+            request_time,
+        ))
+    }
+
+    /// HTTP info for a resource that was not transferred over HTTP
+    fn synthetic(
+        url: String,
+        content_type: &str,
+        body: Vec<u8>,
+        method: Method,
+        request_time: SystemTime,
+    ) -> (Self, Vec<u8>) {
+        let headers = vec![
+            format!("Content-Type: {}", content_type),
+            format!("Content-Length: {}", body.len()),
+        ];
+        let body = match method {
+            Method::Head => Vec::new(),
+            _ => body,
+        };
+        let (text, raw_body) = decode_body(&body);
+
+        let http = HTTP {
+            ip: String::new(),
+            transfer_time: request_time.elapsed().unwrap_or_default(),
+            redirect_count: 0,
+            content_type: content_type.to_string(),
+            response_code: 200,
+            headers,
+            url,
+            body: text,
+            raw_body,
+            request_time,
+            exchanges: Vec::new(),
+        };
+        (http, body)
+    }
+
+    /// Body as received, also when it is not valid UTF-8
+    pub fn body_bytes(&self) -> &[u8] {
+        self.raw_body.as_deref().unwrap_or(self.body.as_bytes())
+    }
+
+    /// Value of the first response header with the given name (case insensitive)
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{WebpageOptions, HTTP};
+    ///
+    /// let http = HTTP::fetch("http://example.org", WebpageOptions::default()).unwrap();
+    /// let server = http.header("server");
+    /// ```
+    pub fn header(&self, name: &str) -> Option<&str> {
+        headers::value(&self.headers, name)
+    }
+
+    /// Links from the `Link` headers, resolved against the effective URL
+    pub fn link_header(&self) -> Vec<LinkRelation> {
+        headers::values(&self.headers, "link")
+            .flat_map(|value| LinkRelation::parse(value, Some(&self.url)))
+            .collect()
+    }
+
+    /// Robots directives from the `X-Robots-Tag` headers
+    pub fn robots(&self) -> Robots {
+        Robots::from_header_values(headers::values(&self.headers, "x-robots-tag"))
+    }
+
+    /// Parsed `Cache-Control` directives
+    pub fn cache_control(&self) -> CacheControl {
+        cache::cache_control(&self.headers)
+    }
+
+    /// `Date` header, the time at which the response was generated
+    pub fn date(&self) -> Option<SystemTime> {
+        cache::date_header(&self.headers, "date")
+    }
+
+    /// `Expires` header
+    pub fn expires(&self) -> Option<SystemTime> {
+        cache::date_header(&self.headers, "expires")
+    }
+
+    /// `Last-Modified` header
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        cache::date_header(&self.headers, "last-modified")
+    }
+
+    /// `Age` header, the time the response spent in upstream caches
+    pub fn age(&self) -> Option<Duration> {
+        cache::age(&self.headers)
+    }
+
+    /// How long the response is fresh after it was generated, from `Cache-Control: max-age`,
+    /// `Expires` or heuristically from `Last-Modified` (RFC 9111, section 4.2.1)
+    pub fn freshness_lifetime(&self) -> Option<Duration> {
+        cache::freshness_lifetime(&self.headers, self.response_code, self.response_time())
+    }
+
+    /// Current age of the response, including the time since it was fetched (RFC 9111, section
+    /// 4.2.3)
+    pub fn current_age(&self) -> Duration {
+        self.current_age_at(SystemTime::now())
+    }
+
+    /// Age of the response at the given time
+    pub fn current_age_at(&self, now: SystemTime) -> Duration {
+        cache::current_age(&self.headers, self.request_time, self.response_time(), now)
+    }
+
+    /// Whether the response can be used without revalidation
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{WebpageOptions, HTTP};
+    ///
+    /// let http = HTTP::fetch("https://example.org", WebpageOptions::default()).unwrap();
+    /// if let Some(lifetime) = http.freshness_lifetime() {
+    ///     println!("refetch in {:?}", lifetime.saturating_sub(http.current_age()));
+    /// }
+    /// ```
+    pub fn is_fresh(&self) -> bool {
+        let cache_control = self.cache_control();
+        if cache_control.no_store() || cache_control.no_cache() {
+            return false;
+        }
+        self.freshness_lifetime()
+            .map_or(false, |lifetime| lifetime > self.current_age())
+    }
+
+    fn response_time(&self) -> SystemTime {
+        self.request_time + self.transfer_time
+    }
+
+    /// Audit the security related response headers
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Severity, WebpageOptions, HTTP};
+    ///
+    /// let http = HTTP::fetch("https://example.org", WebpageOptions::default()).unwrap();
+    /// for finding in http.security_report().findings {
+    ///     if finding.severity >= Severity::Medium {
+    ///         println!("{}: {}", finding.header, finding.message);
+    ///     }
+    /// }
+    /// ```
+    pub fn security_report(&self) -> SecurityReport {
+        SecurityReport::from_headers(&self.headers, self.url.starts_with("https:"))
+    }
+}
+
+/// Limit that aborted a transfer
+///
+/// Available as the inner error of an [`io::Error`] of kind [`io::ErrorKind::TimedOut`]:
+///
+/// ```no_run
+/// use webpage::{Timeout, WebpageOptions, HTTP};
+///
+/// if let Err(e) = HTTP::fetch("http://example.org", WebpageOptions::default()) {
+///     if let Some(Timeout::Connect(duration)) = e.get_ref().and_then(|e| e.downcast_ref()) {
+///         println!("could not connect within {:?}", duration);
+///     }
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Timeout {
+    /// `WebpageOptions.connect_timeout` was exceeded
+    Connect(Duration),
+    /// The transfer was slower than `WebpageOptions.low_speed_limit`
+    LowSpeed {
+        bytes_per_second: u32,
+        duration: Duration,
+    },
+    /// `WebpageOptions.timeout` was exceeded
+    Total(Duration),
+}
+
+impl Timeout {
+    fn from_error(error: &curl::Error, handle: &Easy, options: &WebpageOptions) -> Self {
+        let description = error.extra_description().unwrap_or_default();
+
+        if let Some((bytes_per_second, duration)) = options.low_speed_limit {
+            // "Operation too slow. Less than 1000 bytes/sec transferred the last 1 seconds"
+            if description.contains("too slow") {
+                return Timeout::LowSpeed {
+                    bytes_per_second,
+                    duration,
+                };
+            }
+        }
+        if let Some(connect_timeout) = options.connect_timeout {
+            let connected = handle.connect_time().map_or(false, |t| !t.is_zero());
+            if !connected && connect_timeout <= options.timeout {
+                return Timeout::Connect(connect_timeout);
+            }
+        }
+
+        Timeout::Total(options.timeout)
+    }
+}
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timeout::Connect(duration) => write!(f, "connect timeout of {:?} exceeded", duration),
+            Timeout::LowSpeed {
+                bytes_per_second,
+                duration,
+            } => write!(
+                f,
+                "transfer slower than {} bytes/sec for {:?}",
+                bytes_per_second, duration
+            ),
+            Timeout::Total(duration) => write!(f, "timeout of {:?} exceeded", duration),
+        }
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// Body as text, along with the raw bytes when they are not valid UTF-8
+pub(crate) fn decode_body(body: &[u8]) -> (String, Option<Vec<u8>>) {
+    match std::str::from_utf8(body) {
+        Ok(text) => (text.to_string(), None),
+        Err(_) => (
+            String::from_utf8_lossy(body).into_owned(),
+            Some(body.to_vec()),
+        ),
+    }
+}
+
+/// Guess the content type of a local file from its extension
+fn content_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match &extension[..] {
+        "html" | "htm" => "text/html",
+        "xhtml" => "application/xhtml+xml",
+        "xml" => "application/xml",
+        "rss" => "application/rss+xml",
+        "atom" => "application/atom+xml",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Collects the exchanges of a transfer from the curl debug callback
+struct Recorder {
+    start: Instant,
+    started: SystemTime,
+    hops: Vec<Hop>,
+}
+
+struct Hop {
+    begin: Instant,
+    sent: Instant,
+    first_byte: Option<Instant>,
+    last_byte: Instant,
+    request_headers: Vec<String>,
+    request_body: Vec<u8>,
+    response_headers: Vec<String>,
+    response_body: Vec<u8>,
+}
+
+impl Recorder {
+    fn new(started: SystemTime) -> Self {
+        Self {
+            start: Instant::now(),
+            started,
+            hops: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, info: InfoType, data: &[u8]) {
+        let now = Instant::now();
+        let lines = || {
+            String::from_utf8_lossy(data)
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+        };
+
+        match info {
+            InfoType::HeaderOut => match self.hops.last_mut() {
+                // request headers may be sent in multiple chunks
+                Some(hop) if hop.first_byte.is_none() => hop.request_headers.extend(lines()),
+                _ => {
+                    let begin = self.hops.last().map_or(self.start, |hop| hop.last_byte);
+                    self.hops.push(Hop {
+                        begin,
+                        sent: now,
+                        first_byte: None,
+                        last_byte: now,
+                        request_headers: lines(),
+                        request_body: Vec::new(),
+                        response_headers: Vec::new(),
+                        response_body: Vec::new(),
+                    });
+                }
+            },
+            InfoType::DataOut => {
+                if let Some(hop) = self.hops.last_mut() {
+                    hop.request_body.extend_from_slice(data);
+                }
+            }
+            InfoType::HeaderIn => {
+                if let Some(hop) = self.hops.last_mut() {
+                    hop.first_byte.get_or_insert(now);
+                    hop.last_byte = now;
+                    for line in lines() {
+                        // drop interim (1xx) responses
+                        if line.starts_with("HTTP/") {
+                            hop.response_headers.clear();
+                        }
+                        hop.response_headers.push(line);
+                    }
+                }
+            }
+            InfoType::DataIn => {
+                if let Some(hop) = self.hops.last_mut() {
+                    hop.first_byte.get_or_insert(now);
+                    hop.last_byte = now;
+                    hop.response_body.extend_from_slice(data);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn finish(self, url: &str, body: &[u8], handle: &mut Easy) -> Result<Vec<Exchange>, io::Error> {
+        let num_hops = self.hops.len();
+        let mut exchanges: Vec<Exchange> = Vec::with_capacity(num_hops);
+
+        for (i, hop) in self.hops.into_iter().enumerate() {
+            let last = i + 1 == num_hops;
+
+            let url = match exchanges.last() {
+                _ if last => handle.effective_url()?.unwrap_or(url).to_string(),
+                None => Url::parse(url)
+                    .or_else(|_| Url::parse(&format!("http://{}", url)))
+                    .map_or_else(|_| url.to_string(), |url| url.to_string()),
+                Some(previous) => {
+                    let location = headers::value(&previous.response_headers, "location");
+                    match (Url::parse(&previous.url), location) {
+                        (Ok(base), Some(location)) => base
+                            .join(location)
+                            .map_or_else(|_| location.to_string(), |url| url.to_string()),
+                        _ => previous.url.clone(),
+                    }
+                }
+            };
+
+            let method = hop
+                .request_headers
+                .first()
+                .and_then(|line| line.split(' ').next())
+                .unwrap_or_default()
+                .to_string();
+
+            let first_byte = hop.first_byte.unwrap_or(hop.last_byte);
+            let mut timings = Timings {
+                dns: None,
+                connect: hop.sent.saturating_duration_since(hop.begin),
+                ssl: None,
+                send: Duration::ZERO,
+                wait: first_byte.saturating_duration_since(hop.sent),
+                receive: hop.last_byte.saturating_duration_since(first_byte),
+            };
+            if num_hops == 1 {
+                // curl's timers are only reliable for a transfer without redirects
+                let dns = handle.namelookup_time()?;
+                let connect = handle.connect_time()?;
+                let appconnect = handle.appconnect_time()?;
+                let pretransfer = handle.pretransfer_time()?;
+                let starttransfer = handle.starttransfer_time()?;
+                let established = connect.max(appconnect);
+                timings = Timings {
+                    dns: Some(dns),
+                    connect: established.saturating_sub(dns),
+                    ssl: if appconnect > connect {
+                        Some(appconnect - connect)
+                    } else {
+                        None
+                    },
+                    send: pretransfer.saturating_sub(established),
+                    wait: starttransfer.saturating_sub(pretransfer),
+                    receive: handle.total_time()?.saturating_sub(starttransfer),
+                };
+            }
+
+            exchanges.push(Exchange {
+                started: self.started + hop.begin.saturating_duration_since(self.start),
+                method,
+                url,
+                request_headers: hop.request_headers,
+                request_body: hop.request_body,
+                response_headers: hop.response_headers,
+                // the final body as delivered, rather than the raw data on the wire
+                response_body: if last {
+                    body.to_vec()
+                } else {
+                    hop.response_body
+                },
+                timings,
+            });
+        }
+
+        Ok(exchanges)
     }
 }
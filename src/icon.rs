@@ -0,0 +1,172 @@
+//! Favicons and touch icons
+
+/// Link types of icons, compared case insensitively
+const ICON_RELS: [&str; 4] = [
+    "icon",
+    "apple-touch-icon",
+    "apple-touch-icon-precomposed",
+    "mask-icon",
+];
+
+/// Assumed size of an icon that does not specify its sizes
+const DEFAULT_SIZE: u32 = 16;
+/// Assumed size of an apple-touch-icon that does not specify its sizes
+const DEFAULT_TOUCH_ICON_SIZE: u32 = 180;
+
+/// Site icon from a `<link rel="icon">` (or similar) element, or from the web app manifest
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Icon {
+    /// Resolved URL
+    pub url: String,
+    /// Icon link type, lower cased (e.g. `icon`, `apple-touch-icon`), or `manifest`; a
+    /// `shortcut icon` link is an `icon`
+    pub rel: String,
+    /// Sizes as (width, height) from the `sizes` attribute
+    pub sizes: Vec<(u32, u32)>,
+    /// Whether the icon scales to any size (`sizes="any"` or SVG)
+    pub scalable: bool,
+    /// MIME type from the `type` attribute
+    pub mime_type: Option<String>,
+    /// Color of a monochrome mask-icon
+    pub color: Option<String>,
+}
+
+impl Icon {
+    /// Icon for a link element, if one of the link types in `rel` is an icon
+    pub(crate) fn from_link(
+        rel: &str,
+        url: String,
+        sizes: Option<&str>,
+        mime_type: Option<String>,
+        color: Option<String>,
+    ) -> Option<Self> {
+        let rel = rel.split_ascii_whitespace().find_map(|link_type| {
+            ICON_RELS
+                .iter()
+                .find(|icon_rel| link_type.eq_ignore_ascii_case(icon_rel))
+        })?;
+        Some(Self::new(rel.to_string(), url, sizes, mime_type, color))
+    }
+
+    pub(crate) fn new(
+        rel: String,
+        url: String,
+        sizes: Option<&str>,
+        mime_type: Option<String>,
+        color: Option<String>,
+    ) -> Self {
+        let sizes = sizes.unwrap_or_default();
+        let scalable = sizes
+            .split_whitespace()
+            .any(|size| size.eq_ignore_ascii_case("any"))
+            || mime_type.as_deref() == Some("image/svg+xml")
+            // mask-icons are always SVG
+            || rel == "mask-icon";
+        let sizes = sizes
+            .split_whitespace()
+            .filter_map(|size| {
+                let (width, height) = size.split_once(['x', 'X'])?;
+                Some((width.parse().ok()?, height.parse().ok()?))
+            })
+            .collect();
+
+        Self {
+            url,
+            rel,
+            sizes,
+            scalable,
+            mime_type,
+            color,
+        }
+    }
+
+    /// Fallback `/favicon.ico` at the root of the site
+    pub(crate) fn favicon(url: &url::Url) -> Option<Self> {
+        let url = url.join("/favicon.ico").ok()?;
+        Some(Self::new(
+            "icon".to_string(),
+            url.to_string(),
+            None,
+            None,
+            None,
+        ))
+    }
+
+    /// Largest side of the icon in pixels, estimated when no sizes are given
+    pub fn size(&self) -> u32 {
+        let largest = self.sizes.iter().map(|(w, h)| *w.max(h)).max();
+        largest.unwrap_or(if self.rel.starts_with("apple-touch-icon") {
+            DEFAULT_TOUCH_ICON_SIZE
+        } else {
+            DEFAULT_SIZE
+        })
+    }
+}
+
+/// Pick the icon best suited for display at the given size: a scalable icon, else the smallest
+/// icon at least that large, else the largest one. Monochrome mask-icons are a last resort.
+pub(crate) fn best(icons: &[Icon], size: u32) -> Option<&Icon> {
+    icons.iter().min_by_key(|icon| {
+        let fit = if icon.scalable {
+            (0, 0)
+        } else if icon.size() >= size {
+            (1, icon.size() - size)
+        } else {
+            (2, size - icon.size())
+        };
+        (icon.rel == "mask-icon", fit)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icon(rel: &str, sizes: &str) -> Icon {
+        Icon::from_link(rel, format!("{}-{}", rel, sizes), Some(sizes), None, None).unwrap()
+    }
+
+    #[test]
+    fn test_sizes() {
+        let icon = icon("icon", "16x16 32X32 any");
+        assert_eq!(icon.sizes, vec![(16, 16), (32, 32)]);
+        assert!(icon.scalable);
+        assert_eq!(icon.size(), 32);
+
+        assert!(Icon::from_link("stylesheet", String::new(), None, None, None).is_none());
+        let touch = Icon::from_link("Apple-Touch-Icon", String::new(), None, None, None).unwrap();
+        assert_eq!(touch.size(), 180);
+    }
+
+    #[test]
+    fn test_rel() {
+        for rel in [
+            "Shortcut Icon",
+            "shortcut  icon",
+            "icon shortcut",
+            "alternate\ticon",
+        ] {
+            let icon = Icon::from_link(rel, String::new(), None, None, None).unwrap();
+            assert_eq!(icon.rel, "icon");
+        }
+        assert!(Icon::from_link("shortcut", String::new(), None, None, None).is_none());
+        assert!(Icon::from_link("icons", String::new(), None, None, None).is_none());
+    }
+
+    #[test]
+    fn test_best() {
+        let icons = vec![
+            icon("mask-icon", "any"),
+            icon("icon", "16x16"),
+            icon("icon", "48x48"),
+            icon("apple-touch-icon", "180x180"),
+        ];
+        assert_eq!(best(&icons, 32).unwrap().url, "icon-48x48");
+        assert_eq!(best(&icons, 64).unwrap().url, "apple-touch-icon-180x180");
+        assert_eq!(best(&icons, 512).unwrap().url, "apple-touch-icon-180x180");
+        assert_eq!(best(&icons[..1], 16).unwrap().rel, "mask-icon");
+        assert_eq!(best(&[], 16), None);
+    }
+}
@@ -0,0 +1,274 @@
+//! Images from `<img>`, `<picture>` sources and image inputs
+
+use url::Url;
+
+use crate::parser::resolve;
+
+/// Element declaring an image
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ImageElement {
+    /// \<img\>
+    Img,
+    /// \<source\> inside a \<picture\>
+    Source,
+    /// \<input type="image"\>
+    Input,
+}
+
+/// Image in the document
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Image {
+    /// Element the image is declared by
+    pub element: ImageElement,
+    /// Resolved URL from the `src` attribute
+    pub src: Option<String>,
+    /// Candidates from the `srcset` attribute
+    pub srcset: Vec<ImageCandidate>,
+    /// Source sizes from the `sizes` attribute
+    pub sizes: Vec<SourceSize>,
+    /// Media query of a \<source\>
+    pub media: Option<String>,
+    /// MIME type of a \<source\>
+    pub mime_type: Option<String>,
+    /// Alternative text, empty for decorative images
+    pub alt: Option<String>,
+    /// Advisory text from the `title` attribute, often shown as a tooltip
+    pub title: Option<String>,
+    /// Declared width in pixels
+    pub width: Option<u32>,
+    /// Declared height in pixels
+    pub height: Option<u32>,
+    /// `loading` attribute, lower cased (`lazy` or `eager`)
+    pub loading: Option<String>,
+    /// Resolved URL from `data-src` or `data-lazy`, set by pages loading the image with a script
+    pub lazy_src: Option<String>,
+    /// Candidates from the `data-srcset` attribute
+    pub lazy_srcset: Vec<ImageCandidate>,
+}
+
+/// Image candidate from a `srcset` attribute
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct ImageCandidate {
+    /// Resolved URL
+    pub url: String,
+    /// Width in pixels, from a `w` descriptor
+    pub width: Option<u32>,
+    /// Pixel density, from an `x` descriptor
+    pub density: Option<f64>,
+}
+
+/// Source size from a `sizes` attribute
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct SourceSize {
+    /// Media condition, absent for the default size
+    pub media: Option<String>,
+    /// Length the image is displayed at, e.g. `100vw` or `calc(50vw - 2em)`
+    pub length: String,
+}
+
+impl Image {
+    /// Image for an element, reading its attributes with the given function
+    pub(crate) fn new<F>(element: ImageElement, attribute: F, base: Option<&Url>) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let resolve = |url: String| resolve(base, &url);
+        let srcset = |name| {
+            attribute(name)
+                .map(|srcset| parse_srcset(&srcset, base))
+                .unwrap_or_default()
+        };
+
+        Self {
+            element,
+            src: attribute("src").filter(|src| !src.is_empty()).map(resolve),
+            srcset: srcset("srcset"),
+            sizes: attribute("sizes")
+                .map(|sizes| parse_sizes(&sizes))
+                .unwrap_or_default(),
+            media: attribute("media"),
+            mime_type: attribute("type").filter(|_| element == ImageElement::Source),
+            alt: attribute("alt"),
+            title: attribute("title"),
+            width: attribute("width").and_then(|width| parse_dimension(&width)),
+            height: attribute("height").and_then(|height| parse_dimension(&height)),
+            loading: attribute("loading").map(|loading| loading.to_ascii_lowercase()),
+            lazy_src: attribute("data-src")
+                .or_else(|| attribute("data-lazy"))
+                .filter(|src| !src.is_empty())
+                .map(resolve),
+            lazy_srcset: srcset("data-srcset"),
+        }
+    }
+
+    /// URL of the image as displayed: the lazy-load source, as `src` is often a placeholder until
+    /// a script swaps it in, else `src`, else the first `srcset` candidate
+    pub fn url(&self) -> Option<&str> {
+        self.lazy_src
+            .as_deref()
+            .or(self.src.as_deref())
+            .or_else(|| self.lazy_srcset.first().map(|candidate| &candidate.url[..]))
+            .or_else(|| self.srcset.first().map(|candidate| &candidate.url[..]))
+    }
+}
+
+/// Parse the candidates of a `srcset` attribute, skipping those with invalid descriptors
+fn parse_srcset(srcset: &str, base: Option<&Url>) -> Vec<ImageCandidate> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let end = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let (mut url, mut descriptors) = rest.split_at(end);
+
+        // a URL ending in a comma has no descriptors
+        if url.ends_with(',') {
+            url = url.trim_end_matches(',');
+            rest = descriptors;
+            descriptors = "";
+        } else {
+            let end = descriptors.find(',').unwrap_or(descriptors.len());
+            rest = &descriptors[end..];
+            descriptors = &descriptors[..end];
+        }
+
+        let mut candidate = ImageCandidate {
+            url: resolve(base, url),
+            width: None,
+            density: None,
+        };
+        let valid = descriptors.split_ascii_whitespace().all(|descriptor| {
+            if let Some(width) = descriptor.strip_suffix('w') {
+                candidate.width = width.parse().ok().filter(|width| *width > 0);
+                candidate.width.is_some()
+            } else if let Some(density) = descriptor.strip_suffix('x') {
+                candidate.density = density.parse().ok().filter(|density: &f64| *density >= 0.);
+                candidate.density.is_some()
+            } else if let Some(height) = descriptor.strip_suffix('h') {
+                // the height descriptor is reserved for future use, and ignored
+                height.parse::<u32>().is_ok()
+            } else {
+                false
+            }
+        });
+        if valid && !(candidate.width.is_some() && candidate.density.is_some()) {
+            candidates.push(candidate);
+        }
+    }
+
+    candidates
+}
+
+/// Parse a `sizes` attribute into its media conditions and lengths
+fn parse_sizes(sizes: &str) -> Vec<SourceSize> {
+    sizes
+        .split(',')
+        .map(str::trim)
+        .filter(|size| !size.is_empty())
+        .map(|size| {
+            // the length is the last component, which may be a function like calc()
+            let start = if size.ends_with(')') {
+                let mut depth = 0;
+                let open = size.char_indices().rev().find_map(|(i, c)| {
+                    match c {
+                        ')' => depth += 1,
+                        '(' => depth -= 1,
+                        _ => (),
+                    }
+                    (depth == 0).then_some(i)
+                });
+                let open = open.unwrap_or(0);
+                size[..open]
+                    .rfind(|c: char| c.is_ascii_whitespace() || c == ')')
+                    .map_or(0, |i| i + 1)
+            } else {
+                size.rfind(|c: char| c.is_ascii_whitespace())
+                    .map_or(0, |i| i + 1)
+            };
+
+            let media = size[..start].trim();
+            SourceSize {
+                media: Some(media.to_string()).filter(|media| !media.is_empty()),
+                length: size[start..].to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Leading digits of a width or height attribute, like browsers parse `100px` as 100
+fn parse_dimension(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srcset() {
+        let base = Url::parse("https://example.org/blog/").unwrap();
+        let candidates = parse_srcset(
+            " small.jpg 480w, /large.jpg 1080w,retina.jpg 2x,data:image/png;base64,AAA=, \
+             plain.jpg, bad.jpg 10q, both.jpg 1x 100w",
+            Some(&base),
+        );
+        let urls: Vec<_> = candidates.iter().map(|c| &c.url[..]).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.org/blog/small.jpg",
+                "https://example.org/large.jpg",
+                "https://example.org/blog/retina.jpg",
+                "data:image/png;base64,AAA=",
+                "https://example.org/blog/plain.jpg",
+            ]
+        );
+        assert_eq!(candidates[0].width, Some(480));
+        assert_eq!(candidates[2].density, Some(2.));
+        assert_eq!(candidates[4].width, None);
+        assert_eq!(candidates[4].density, None);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let sizes = parse_sizes(
+            "(max-width: 600px) 100vw, (min-width: 601px) and (max-width: 900px) calc(50vw - 2em), 33vw",
+        );
+        assert_eq!(
+            sizes,
+            vec![
+                SourceSize {
+                    media: Some("(max-width: 600px)".into()),
+                    length: "100vw".into()
+                },
+                SourceSize {
+                    media: Some("(min-width: 601px) and (max-width: 900px)".into()),
+                    length: "calc(50vw - 2em)".into()
+                },
+                SourceSize {
+                    media: None,
+                    length: "33vw".into()
+                },
+            ]
+        );
+        assert_eq!(parse_dimension(" 100px"), Some(100));
+        assert_eq!(parse_dimension("auto"), None);
+    }
+}
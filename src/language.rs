@@ -0,0 +1,210 @@
+//! Language resolution from the document, the `Content-Language` header or the text itself
+
+/// Scripts that are (nearly) only used for a single language, with their code point ranges
+const SCRIPTS: [(&str, u32, u32); 19] = [
+    ("el", 0x0370, 0x03FF), // Greek
+    ("hy", 0x0530, 0x058F), // Armenian
+    ("he", 0x0590, 0x05FF), // Hebrew
+    ("hi", 0x0900, 0x097F), // Devanagari
+    ("bn", 0x0980, 0x09FF), // Bengali
+    ("pa", 0x0A00, 0x0A7F), // Gurmukhi
+    ("gu", 0x0A80, 0x0AFF), // Gujarati
+    ("ta", 0x0B80, 0x0BFF), // Tamil
+    ("te", 0x0C00, 0x0C7F), // Telugu
+    ("kn", 0x0C80, 0x0CFF), // Kannada
+    ("ml", 0x0D00, 0x0D7F), // Malayalam
+    ("si", 0x0D80, 0x0DFF), // Sinhala
+    ("th", 0x0E00, 0x0E7F), // Thai
+    ("lo", 0x0E80, 0x0EFF), // Lao
+    ("my", 0x1000, 0x109F), // Myanmar
+    ("ka", 0x10A0, 0x10FF), // Georgian
+    ("km", 0x1780, 0x17FF), // Khmer
+    ("ja", 0x3040, 0x30FF), // Hiragana and Katakana
+    ("ko", 0xAC00, 0xD7AF), // Hangul syllables
+];
+
+/// Where the language of a document was found
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LanguageSource {
+    /// `lang` attribute on \<html\> or \<body\>
+    Attribute,
+    /// \<meta http-equiv="content-language"\>
+    Meta,
+    /// `Content-Language` response header
+    Header,
+    /// Guessed from the script of the text content
+    Detected,
+}
+
+/// Language with its BCP 47 subtags
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Language {
+    /// Normalized tag, e.g. `en-US` or `zh-Hant-TW`
+    pub tag: String,
+    /// Primary language subtag, lower cased
+    pub language: String,
+    /// Script subtag, title cased
+    pub script: Option<String>,
+    /// Region subtag, upper cased
+    pub region: Option<String>,
+    /// Where the language was found
+    pub source: LanguageSource,
+}
+
+impl Language {
+    /// Parse and normalize a BCP 47 language tag, using the first one of a comma separated list
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::{Language, LanguageSource};
+    ///
+    /// let language = Language::parse("pt_br", LanguageSource::Attribute).unwrap();
+    /// assert_eq!(language.tag, "pt-BR");
+    /// assert_eq!(language.region, Some("BR".to_string()));
+    /// ```
+    pub fn parse(input: &str, source: LanguageSource) -> Option<Self> {
+        let input = input.split(',').next()?.trim();
+        let mut subtags = input.split(['-', '_']);
+
+        let language = subtags.next()?.to_ascii_lowercase();
+        let valid =
+            (2..=8).contains(&language.len()) && language.bytes().all(|b| b.is_ascii_alphabetic());
+        if !valid {
+            return None;
+        }
+
+        let mut script = None;
+        let mut region = None;
+        let mut tag = language.clone();
+        // script and region are only recognized directly after the language, in that order
+        let mut in_prefix = true;
+        for subtag in subtags {
+            if subtag.is_empty() || !subtag.bytes().all(|b| b.is_ascii_alphanumeric()) {
+                return None;
+            }
+            let alphabetic = subtag.bytes().all(|b| b.is_ascii_alphabetic());
+            let numeric = subtag.bytes().all(|b| b.is_ascii_digit());
+
+            tag.push('-');
+            if in_prefix && script.is_none() && region.is_none() && alphabetic && subtag.len() == 4
+            {
+                let normalized =
+                    subtag[..1].to_ascii_uppercase() + &subtag[1..].to_ascii_lowercase();
+                tag.push_str(&normalized);
+                script = Some(normalized);
+            } else if in_prefix
+                && region.is_none()
+                && ((alphabetic && subtag.len() == 2) || (numeric && subtag.len() == 3))
+            {
+                let normalized = subtag.to_ascii_uppercase();
+                tag.push_str(&normalized);
+                region = Some(normalized);
+            } else {
+                // variants and extensions
+                in_prefix = false;
+                tag.push_str(&subtag.to_ascii_lowercase());
+            }
+        }
+
+        Some(Self {
+            tag,
+            language,
+            script,
+            region,
+            source,
+        })
+    }
+
+    /// Guess the language from text written in a script used by a single language
+    ///
+    /// Returns `None` for the Latin, Cyrillic, Arabic and Han scripts, which are shared by many
+    /// languages.
+    pub fn detect(text: &str) -> Option<Self> {
+        let mut counts = [0; SCRIPTS.len()];
+        let mut letters = 0;
+        for c in text.chars().filter(|c| c.is_alphabetic()) {
+            letters += 1;
+            let code = c as u32;
+            if let Some(index) = SCRIPTS
+                .iter()
+                .position(|(_, from, to)| (*from..=*to).contains(&code))
+            {
+                counts[index] += 1;
+            }
+        }
+
+        let (index, count) = counts.iter().enumerate().max_by_key(|(_, count)| **count)?;
+        // Japanese mixes kana with Han characters, which are otherwise not counted
+        let threshold = if SCRIPTS[index].0 == "ja" { 10 } else { 2 };
+        if *count == 0 || count * threshold < letters {
+            return None;
+        }
+
+        Self::parse(SCRIPTS[index].0, LanguageSource::Detected)
+    }
+}
+
+/// Resolve the language, in order of precedence: `lang` attribute, content-language meta tag,
+/// `Content-Language` header, detection from the text
+pub(crate) fn resolve(
+    attribute: Option<&str>,
+    meta: Option<&str>,
+    header: Option<&str>,
+    text: &str,
+) -> Option<Language> {
+    attribute
+        .and_then(|tag| Language::parse(tag, LanguageSource::Attribute))
+        .or_else(|| meta.and_then(|tag| Language::parse(tag, LanguageSource::Meta)))
+        .or_else(|| header.and_then(|tag| Language::parse(tag, LanguageSource::Header)))
+        .or_else(|| Language::detect(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |tag| Language::parse(tag, LanguageSource::Header).map(|l| l.tag);
+        assert_eq!(parse("EN"), Some("en".to_string()));
+        assert_eq!(parse("en-us"), Some("en-US".to_string()));
+        assert_eq!(parse("de-DE, en"), Some("de-DE".to_string()));
+        assert_eq!(parse("es-419"), Some("es-419".to_string()));
+        assert_eq!(parse("sl-rozaj-biske"), Some("sl-rozaj-biske".to_string()));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("en--US"), None);
+        assert_eq!(parse("1234"), None);
+
+        let language = Language::parse("ZH_hant_tw", LanguageSource::Meta).unwrap();
+        assert_eq!(language.tag, "zh-Hant-TW");
+        assert_eq!(language.language, "zh");
+        assert_eq!(language.script, Some("Hant".to_string()));
+        assert_eq!(language.region, Some("TW".to_string()));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let resolved = resolve(None, Some("nl"), Some("en"), "").unwrap();
+        assert_eq!(resolved.tag, "nl");
+        assert_eq!(resolved.source, LanguageSource::Meta);
+
+        let resolved = resolve(Some("invalid!"), None, Some("en-gb"), "").unwrap();
+        assert_eq!(resolved.tag, "en-GB");
+        assert_eq!(resolved.source, LanguageSource::Header);
+
+        let resolved = resolve(None, None, None, "Καλημέρα κόσμε").unwrap();
+        assert_eq!(resolved.tag, "el");
+        assert_eq!(resolved.source, LanguageSource::Detected);
+
+        assert_eq!(
+            resolve(None, None, None, "東京は日本の首都です")
+                .unwrap()
+                .tag,
+            "ja"
+        );
+        assert_eq!(resolve(None, None, None, "Hello world"), None);
+    }
+}
@@ -26,7 +26,14 @@
 //! assert_eq!(html.opengraph.og_type, "website".to_string());
 //! ```
 //!
-//! You can also get HTML info about local data:
+//! Local files and inline data can be processed via `file:` and `data:` URLs, or parsed directly
+//! for HTML info:
+//!
+//! ```rust
+//! use webpage::{Webpage, WebpageOptions};
+//! let info = Webpage::from_url("data:text/html,<title>Hello</title>", WebpageOptions::default());
+//! assert_eq!(info.unwrap().html.title, Some("Hello".to_string()));
+//! ```
 //!
 //! ```rust
 //! use webpage::HTML;
@@ -38,13 +45,23 @@
 //!
 //! The following configurations are available:
 //! ```rust
+//! # use webpage::PemSource;
 //! pub struct WebpageOptions {
 //!     allow_insecure: bool,
 //!     follow_location: bool,
 //!     max_redirections: u32,
 //!     timeout: std::time::Duration,
+//!     connect_timeout: Option<std::time::Duration>,
+//!     low_speed_limit: Option<(u32, std::time::Duration)>,
+//!     max_recv_speed: Option<u64>,
 //!     useragent: String,
 //!     headers: Vec<String>,
+//!     ca_certificates: Option<PemSource>,
+//!     client_certificate: Option<PemSource>,
+//!     client_key: Option<PemSource>,
+//!     client_key_password: Option<String>,
+//!     pinned_public_keys: Vec<String>,
+//!     record_transfer: bool,
 //! }
 //! ```
 //!
@@ -55,23 +72,120 @@
 //! options.allow_insecure = true;
 //! let info = Webpage::from_url("https://example.org", options).expect("Halp, could not fetch");
 //! ```
+//!
+//! ## Client
+//!
+//! When fetching many pages, a [`Client`] reuses connections, DNS lookups and cookies:
+//!
+//! ```no_run
+//! use webpage::{Client, WebpageOptions};
+//!
+//! let client = Client::new(WebpageOptions::default());
+//! let info = client.fetch("https://example.org").expect("Could not read from URL");
+//! ```
+//!
+//! ## Requests
+//!
+//! Other request methods and bodies are supported via [`Request`]:
+//!
+//! ```no_run
+//! use webpage::{Request, Webpage, WebpageOptions};
+//!
+//! let request = Request::post("https://example.org/search").form(&[("q", "rust")]);
+//! let info = Webpage::from_request(request, WebpageOptions::default());
+//! ```
 
 mod html;
 pub use html::{Link, HTML};
 
+mod article;
+pub use article::Article;
+
+mod extractor;
+pub use extractor::{Element, Extensions, Extractor, Segment, Text};
+
+mod markdown;
+pub use markdown::{LinkStyle, MarkdownOptions};
+
 #[cfg(feature = "curl")]
 mod http;
 #[cfg(feature = "curl")]
-pub use http::HTTP;
+pub use http::{Exchange, Timeout, Timings, HTTP};
+
+#[cfg(feature = "curl")]
+mod har;
+
+#[cfg(feature = "curl")]
+mod warc;
+#[cfg(feature = "curl")]
+pub use warc::{WarcReader, WarcRecord, WarcWriter};
+
+#[cfg(feature = "curl")]
+mod client;
+#[cfg(feature = "curl")]
+pub use client::Client;
+
+#[cfg(feature = "curl")]
+mod request;
+#[cfg(feature = "curl")]
+pub use request::{Method, Request};
+
+mod cache;
+pub use cache::CacheControl;
+
+mod link_header;
+pub use link_header::LinkRelation;
+
+mod heading;
+pub use heading::{Heading, HeadingIssue, OutlineEntry};
+
+mod icon;
+pub use icon::Icon;
+
+mod image;
+pub use image::{Image, ImageCandidate, ImageElement, SourceSize};
+
+mod language;
+pub use language::{Language, LanguageSource};
+
+mod feed;
+pub use feed::{Enclosure, Feed, FeedItem, FeedKind};
+
+mod sitemap;
+pub use sitemap::{Sitemap, SitemapEntry, SitemapImage, SitemapNews, SitemapVideo};
+
+mod robots;
+pub use robots::{Robots, RobotsDirectives};
+
+mod manifest;
+pub use manifest::Manifest;
+
+mod oembed;
+pub use oembed::{OEmbed, OEmbedEndpoint, OEmbedFormat};
 
 mod opengraph;
 pub use opengraph::{Opengraph, OpengraphObject};
 
+mod twitter_card;
+pub use twitter_card::{TwitterApp, TwitterCard};
+
 mod schema_org;
 pub use schema_org::SchemaOrg;
 
+mod security;
+pub use security::{
+    ContentSecurityPolicy, Finding, SecurityReport, Severity, StrictTransportSecurity,
+};
+
+mod date;
+mod headers;
 mod parser;
+mod sink;
+mod text;
+mod xml;
 
+#[cfg(feature = "curl")]
+use std::path::PathBuf;
 #[cfg(feature = "curl")]
 use std::time::Duration;
 
@@ -89,10 +203,12 @@ pub struct Webpage {
     pub http: HTTP,
     /// info from the parsed HTML doc
     pub html: HTML,
+    /// web app manifest, see [`Webpage::fetch_manifest`]
+    pub manifest: Option<Manifest>,
 }
 
 /// Configuration options for fetching a webpage
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[cfg(feature = "curl")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[non_exhaustive]
@@ -105,10 +221,42 @@ pub struct WebpageOptions {
     pub max_redirections: u32,
     /// Timeout for the HTTP request \[10 secs\]
     pub timeout: Duration,
+    /// Timeout for resolving and connecting to the host, bounded by `timeout` \[None\]
+    pub connect_timeout: Option<Duration>,
+    /// Abort when the transfer is slower than the given bytes per second for the given
+    /// duration \[None\]
+    pub low_speed_limit: Option<(u32, Duration)>,
+    /// Maximum receive rate in bytes per second \[None\]
+    pub max_recv_speed: Option<u64>,
     /// User agent string used for the request \[webpage-rs - <https://crates.io/crates/webpage>\]
     pub useragent: String,
     /// Custom HTTP headers to send with the request
     pub headers: Vec<String>,
+    /// CA bundle to verify the peer with, instead of the system default \[None\]
+    pub ca_certificates: Option<PemSource>,
+    /// Client certificate for mutual TLS \[None\]
+    pub client_certificate: Option<PemSource>,
+    /// Private key belonging to the client certificate \[None\]
+    pub client_key: Option<PemSource>,
+    /// Passphrase for the private key \[None\]
+    pub client_key_password: Option<String>,
+    /// Public keys the server must present, as `sha256//<base64>` hashes or a path to a PEM/DER
+    /// file \[empty\]
+    pub pinned_public_keys: Vec<String>,
+    /// Keep every request and response of the transfer in `HTTP.exchanges`, e.g. for HAR
+    /// export \[false\]
+    pub record_transfer: bool,
+}
+
+/// PEM encoded TLS material, either on disk or in memory
+#[derive(Debug, Clone)]
+#[cfg(feature = "curl")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PemSource {
+    /// Path to a PEM file
+    Path(PathBuf),
+    /// PEM contents
+    Bytes(Vec<u8>),
 }
 
 #[cfg(feature = "curl")]
@@ -119,8 +267,17 @@ impl Default for WebpageOptions {
             follow_location: true,
             max_redirections: 5,
             timeout: Duration::from_secs(10),
+            connect_timeout: None,
+            low_speed_limit: None,
+            max_recv_speed: None,
             useragent: "webpage-rs - https://crates.io/crates/webpage".to_string(),
             headers: Vec::new(),
+            ca_certificates: None,
+            client_certificate: None,
+            client_key: None,
+            client_key_password: None,
+            pinned_public_keys: Vec::new(),
+            record_transfer: false,
         }
     }
 }
@@ -129,6 +286,8 @@ impl Default for WebpageOptions {
 impl Webpage {
     /// Fetch a webpage from the given URL, and extract HTML info
     ///
+    /// Local `file:` and inline `data:` URLs are supported as well.
+    ///
     /// ## Examples
     /// ```
     /// use webpage::{Webpage, WebpageOptions};
@@ -137,10 +296,56 @@ impl Webpage {
     /// assert!(info.is_ok())
     /// ```
     pub fn from_url(url: &str, options: WebpageOptions) -> Result<Self, std::io::Error> {
-        let http = HTTP::fetch(url, options)?;
+        Client::new(options).fetch(url)
+    }
 
-        let html = HTML::from_string(http.body.clone(), Some(http.url.clone()))?;
+    /// Send a custom request, and extract HTML info from the response
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Request, Webpage, WebpageOptions};
+    ///
+    /// let request = Request::post("https://example.org/search").form(&[("q", "rust")]);
+    /// let info = Webpage::from_request(request, WebpageOptions::default());
+    /// assert!(info.is_ok())
+    /// ```
+    pub fn from_request(request: Request, options: WebpageOptions) -> Result<Self, std::io::Error> {
+        Client::new(options).fetch_request(request)
+    }
+
+    /// Language of the page, from the document, the `Content-Language` header or detected from
+    /// the text
+    pub fn language(&self) -> Option<Language> {
+        self.html
+            .resolve_language(self.http.header("content-language"))
+    }
+
+    /// Robots directives from the `X-Robots-Tag` headers combined with those in the document
+    pub fn robots(&self) -> Robots {
+        let mut robots = self.html.robots.clone();
+        robots.merge(&self.http.robots());
+        robots
+    }
+
+    /// Whether search engines may index this page, according to the robots directives
+    ///
+    /// Pass a crawler name (e.g. `googlebot`) to include directives addressing that crawler.
+    pub fn is_indexable(&self, crawler: Option<&str>) -> bool {
+        self.directives(crawler).is_indexable()
+    }
 
-        Ok(Self { http, html })
+    /// Whether crawlers may follow the links on this page, according to the robots directives
+    ///
+    /// Pass a crawler name (e.g. `googlebot`) to include directives addressing that crawler.
+    pub fn is_followable(&self, crawler: Option<&str>) -> bool {
+        self.directives(crawler).is_followable()
+    }
+
+    fn directives(&self, crawler: Option<&str>) -> RobotsDirectives {
+        let robots = self.robots();
+        match crawler {
+            Some(name) => robots.for_crawler(name),
+            None => robots.all,
+        }
     }
 }
@@ -0,0 +1,226 @@
+//! The HTTP `Link` header, see [RFC 8288](https://www.rfc-editor.org/rfc/rfc8288)
+
+use url::Url;
+
+use crate::parser::FEED_TYPES;
+use crate::HTML;
+
+/// A typed link from the HTTP `Link` header
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct LinkRelation {
+    /// Target URL, resolved against the base URL when given
+    pub url: String,
+    /// Relation types (lower cased), e.g. `canonical`, `alternate`, `next`, `preload`
+    pub rel: Vec<String>,
+    /// Other target attributes (`type`, `title`, `hreflang`, `anchor`, ..) with lower cased names
+    pub attributes: Vec<(String, String)>,
+}
+
+impl LinkRelation {
+    /// Parse a `Link` header value, resolving the targets against `base`
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::LinkRelation;
+    ///
+    /// let links = LinkRelation::parse(
+    ///     r#"</feed>; rel="alternate"; type="application/rss+xml", <https://example.org/b>; rel=canonical"#,
+    ///     Some("https://example.org/a"),
+    /// );
+    /// assert_eq!(links.len(), 2);
+    /// assert_eq!(links[0].url, "https://example.org/feed");
+    /// assert_eq!(links[0].attribute("type"), Some("application/rss+xml"));
+    /// assert!(links[1].has_rel("canonical"));
+    /// ```
+    pub fn parse(value: &str, base: Option<&str>) -> Vec<Self> {
+        let base = base.and_then(|base| Url::parse(base).ok());
+        let mut links = Vec::new();
+        let mut rest = value;
+
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+            if !rest.starts_with('<') {
+                break;
+            }
+            let end = match rest.find('>') {
+                Some(end) => end,
+                None => break,
+            };
+            let target = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            let mut rel = Vec::new();
+            let mut attributes = Vec::new();
+            while let Some(params) = rest.trim_start().strip_prefix(';') {
+                let (name, value, remainder) = parse_param(params);
+                rest = remainder;
+                if name.is_empty() {
+                    continue;
+                }
+                if name == "rel" {
+                    // only the first occurrence of rel is used
+                    if rel.is_empty() {
+                        rel = value
+                            .split_ascii_whitespace()
+                            .map(str::to_ascii_lowercase)
+                            .collect();
+                    }
+                } else {
+                    attributes.push((name, value));
+                }
+            }
+
+            let url = match base {
+                Some(ref base) => base
+                    .join(target)
+                    .map_or_else(|_| target.to_string(), |url| url.to_string()),
+                None => target.to_string(),
+            };
+            links.push(Self {
+                url,
+                rel,
+                attributes,
+            });
+        }
+
+        links
+    }
+
+    /// Whether the link has the given relation type
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rel.iter().any(|r| r.eq_ignore_ascii_case(rel))
+    }
+
+    /// Value of a target attribute
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| &value[..])
+    }
+}
+
+/// Parse a single `name=value` or `name="quoted value"` parameter, returns the lower cased name,
+/// the value and the remaining input
+fn parse_param(input: &str) -> (String, String, &str) {
+    let input = input.trim_start();
+    let name_end = input.find(['=', ';', ',']).unwrap_or(input.len());
+    let name = input[..name_end]
+        .trim()
+        .trim_end_matches('*')
+        .to_ascii_lowercase();
+    let rest = &input[name_end..];
+
+    let rest = match rest.strip_prefix('=') {
+        Some(rest) => rest.trim_start(),
+        None => return (name, String::new(), rest),
+    };
+
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                }
+                '"' => return (name, value, &quoted[i + 1..]),
+                _ => value.push(c),
+            }
+        }
+        (name, value, "")
+    } else {
+        let end = rest.find([';', ',']).unwrap_or(rest.len());
+        (name, rest[..end].trim().to_string(), &rest[end..])
+    }
+}
+
+/// Use the header links as fallback for the canonical URL, feed and pagination of the document
+/// fetched from `url`
+#[cfg_attr(not(feature = "curl"), allow(dead_code))]
+pub(crate) fn merge(html: &mut HTML, links: &[LinkRelation], url: &str) {
+    // links with an anchor describe another resource
+    let own = |link: &&LinkRelation| link.attribute("anchor").map_or(true, str::is_empty);
+    let first = |rel: &str| {
+        links
+            .iter()
+            .filter(own)
+            .find(|link| link.has_rel(rel))
+            .map(|link| link.url.clone())
+    };
+
+    // the URL is only set to the fetched URL when the document has no canonical link
+    if html.url.as_deref() == Some(url) {
+        if let Some(canonical) = first("canonical") {
+            html.set_url(Some(canonical));
+        }
+    }
+    if html.feed.is_none() {
+        html.feed = links
+            .iter()
+            .filter(own)
+            .find(|link| {
+                link.has_rel("alternate")
+                    && FEED_TYPES.contains(&link.attribute("type").unwrap_or_default())
+            })
+            .map(|link| link.url.clone());
+    }
+    if html.next.is_none() {
+        html.next = first("next");
+    }
+    if html.prev.is_none() {
+        html.prev = first("prev").or_else(|| first("previous"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let links = LinkRelation::parse(
+            r#"<https://cdn.example/style.css>; rel=preload; as=style, </page/3>; rel="next"; title="Page \"3\"", <;,>; rel="Alternate Stylesheet""#,
+            Some("https://example.org/page/2"),
+        );
+        assert_eq!(links.len(), 3);
+
+        assert_eq!(links[0].url, "https://cdn.example/style.css");
+        assert_eq!(links[0].rel, vec!["preload"]);
+        assert_eq!(links[0].attribute("as"), Some("style"));
+
+        assert_eq!(links[1].url, "https://example.org/page/3");
+        assert_eq!(links[1].attribute("title"), Some("Page \"3\""));
+
+        assert_eq!(links[2].url, "https://example.org/page/;,");
+        assert_eq!(links[2].rel, vec!["alternate", "stylesheet"]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let url = "https://example.org/a";
+        let links = LinkRelation::parse(
+            r#"</canonical>; rel=canonical, </feed.xml>; rel=alternate; type="application/rss+xml", </b>; rel=next, </other>; rel=canonical; anchor="/x""#,
+            Some(url),
+        );
+
+        let mut html = HTML::from_string(String::new(), Some(url.to_string())).unwrap();
+        merge(&mut html, &links, url);
+        assert_eq!(html.url, Some("https://example.org/canonical".to_string()));
+        assert_eq!(html.feed, Some("https://example.org/feed.xml".to_string()));
+        assert_eq!(html.next, Some("https://example.org/b".to_string()));
+        assert_eq!(html.prev, None);
+
+        // the document takes precedence
+        let input =
+            r#"<link rel="canonical" href="https://example.org/doc"><link rel="next" href="/c">"#;
+        let mut html = HTML::from_string(input.to_string(), Some(url.to_string())).unwrap();
+        merge(&mut html, &links, url);
+        assert_eq!(html.url, Some("https://example.org/doc".to_string()));
+        assert_eq!(html.next, Some("https://example.org/c".to_string()));
+    }
+}
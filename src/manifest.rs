@@ -0,0 +1,208 @@
+//! Web app manifest, see <https://www.w3.org/TR/appmanifest/>
+
+use std::io;
+
+use serde_json::Value;
+
+use crate::icon::Icon;
+#[cfg(feature = "curl")]
+use crate::{Client, Request, Webpage, WebpageOptions};
+
+/// Parsed web app manifest
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Manifest {
+    pub name: Option<String>,
+    pub short_name: Option<String>,
+    pub description: Option<String>,
+    /// Resolved start URL
+    pub start_url: Option<String>,
+    /// Resolved identity of the app
+    pub id: Option<String>,
+    /// Resolved navigation scope
+    pub scope: Option<String>,
+    /// Display mode: fullscreen, standalone, minimal-ui or browser
+    pub display: Option<String>,
+    pub orientation: Option<String>,
+    pub theme_color: Option<String>,
+    pub background_color: Option<String>,
+    pub lang: Option<String>,
+    /// Text direction: ltr, rtl or auto
+    pub dir: Option<String>,
+    pub categories: Vec<String>,
+    /// Icons with resolved URLs, `rel` is set to `manifest`
+    pub icons: Vec<Icon>,
+}
+
+impl Manifest {
+    /// Parse a manifest, resolving URLs against the URL of the manifest
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::Manifest;
+    ///
+    /// let input = r#"{"name": "Example", "start_url": "/?pwa", "icons": [
+    ///     {"src": "icon.png", "sizes": "192x192", "type": "image/png"}
+    /// ]}"#;
+    /// let manifest = Manifest::parse(input, Some("https://example.org/app/manifest.json")).unwrap();
+    /// assert_eq!(manifest.start_url, Some("https://example.org/?pwa".to_string()));
+    /// assert_eq!(manifest.icons[0].url, "https://example.org/app/icon.png");
+    /// ```
+    pub fn parse(input: &str, url: Option<&str>) -> Result<Self, io::Error> {
+        let value: Value = serde_json::from_str(input.trim_start_matches('\u{feff}'))?;
+        if !value.is_object() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "manifest is not a JSON object",
+            ));
+        }
+
+        let base = url.and_then(|url| url::Url::parse(url).ok());
+        let resolve = |value: &Value| {
+            let url = value.as_str()?.trim();
+            match &base {
+                Some(base) => base.join(url).ok().map(String::from),
+                None => Some(url.to_string()),
+            }
+        };
+        let string = |value: &Value| value.as_str().map(|s| s.trim().to_string());
+
+        let icons = value["icons"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|icon| {
+                Some(Icon::new(
+                    "manifest".to_string(),
+                    resolve(&icon["src"])?,
+                    icon["sizes"].as_str(),
+                    string(&icon["type"]),
+                    None,
+                ))
+            })
+            .collect();
+        let start_url = resolve(&value["start_url"]);
+
+        Ok(Self {
+            name: string(&value["name"]),
+            short_name: string(&value["short_name"]),
+            description: string(&value["description"]),
+            // the id defaults to the start URL
+            id: resolve(&value["id"]).or_else(|| start_url.clone()),
+            start_url,
+            scope: resolve(&value["scope"]),
+            display: string(&value["display"]),
+            orientation: string(&value["orientation"]),
+            theme_color: string(&value["theme_color"]),
+            background_color: string(&value["background_color"]),
+            lang: string(&value["lang"]),
+            dir: string(&value["dir"]),
+            categories: value["categories"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(string)
+                .collect(),
+            icons,
+        })
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Manifest {
+    /// Fetch and parse a manifest
+    pub fn fetch(url: &str, options: WebpageOptions) -> Result<Self, io::Error> {
+        Client::new(options).fetch_manifest(url)
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Client {
+    /// Fetch and parse a manifest
+    pub fn fetch_manifest(&self, url: &str) -> Result<Manifest, io::Error> {
+        let http = self.send(Request::get(url))?;
+        if !(200..300).contains(&http.response_code) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("HTTP status {} for manifest {}", http.response_code, url),
+            ));
+        }
+        Manifest::parse(&http.body, Some(&http.url))
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Webpage {
+    /// Fetch the manifest linked from this page, if any, and attach it as `Webpage.manifest`
+    ///
+    /// The manifest icons are added to `HTML.icons`.
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Webpage, WebpageOptions};
+    ///
+    /// let mut webpage = Webpage::from_url("https://example.org", WebpageOptions::default()).unwrap();
+    /// if let Ok(Some(manifest)) = webpage.fetch_manifest(WebpageOptions::default()) {
+    ///     println!("{:?} {:?}", manifest.name, manifest.theme_color);
+    /// }
+    /// ```
+    pub fn fetch_manifest(
+        &mut self,
+        options: WebpageOptions,
+    ) -> Result<Option<&Manifest>, io::Error> {
+        let url = match self.html.manifest {
+            Some(ref url) => url,
+            None => return Ok(None),
+        };
+        let manifest = Manifest::fetch(url, options)?;
+
+        self.html.icons.retain(|icon| icon.rel != "manifest");
+        self.html.icons.extend(manifest.icons.iter().cloned());
+        self.manifest = Some(manifest);
+
+        Ok(self.manifest.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let input = r##"{
+            "name": "Example App",
+            "short_name": "Example",
+            "start_url": "./?source=pwa",
+            "scope": "/app/",
+            "display": "standalone",
+            "theme_color": "#336699",
+            "categories": ["news", 1],
+            "icons": [
+                {"src": "/icon-192.png", "sizes": "192x192", "type": "image/png"},
+                {"src": "icon.svg", "sizes": "any", "purpose": "maskable"},
+                {"sizes": "512x512"}
+            ]
+        }"##;
+        let manifest = Manifest::parse(input, Some("https://example.org/app/manifest.json"));
+        let manifest = manifest.unwrap();
+        assert_eq!(manifest.short_name, Some("Example".to_string()));
+        assert_eq!(
+            manifest.start_url,
+            Some("https://example.org/app/?source=pwa".to_string())
+        );
+        assert_eq!(manifest.id, manifest.start_url);
+        assert_eq!(manifest.scope, Some("https://example.org/app/".to_string()));
+        assert_eq!(manifest.theme_color, Some("#336699".to_string()));
+        assert_eq!(manifest.categories, vec!["news"]);
+
+        assert_eq!(manifest.icons.len(), 2);
+        assert_eq!(manifest.icons[0].url, "https://example.org/icon-192.png");
+        assert_eq!(manifest.icons[0].sizes, vec![(192, 192)]);
+        assert!(manifest.icons[1].scalable);
+
+        assert!(Manifest::parse("[]", None).is_err());
+        assert!(Manifest::parse("not json", None).is_err());
+    }
+}
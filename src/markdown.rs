@@ -0,0 +1,722 @@
+//! Render the document as CommonMark, with GitHub flavored tables
+
+use url::Url;
+
+use crate::html::HTML;
+use crate::parser;
+use crate::sink::{Document, NodeData, NodeId};
+#[cfg(feature = "curl")]
+use crate::Webpage;
+
+/// Elements that are left out entirely
+const SKIP_TAGS: [&str; 13] = [
+    "button", "canvas", "head", "iframe", "input", "noscript", "object", "script", "select",
+    "style", "svg", "template", "textarea",
+];
+
+/// Inline elements, other elements start a new block
+const INLINE_TAGS: [&str; 22] = [
+    "abbr", "b", "bdi", "bdo", "cite", "data", "del", "dfn", "font", "ins", "kbd", "label", "mark",
+    "q", "s", "samp", "small", "span", "sub", "sup", "time", "u",
+];
+
+/// How links are written
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LinkStyle {
+    /// `[text](url)`
+    Inline,
+    /// `[text][1]`, with `[1]: url` at the end
+    Reference,
+}
+
+/// Options for rendering Markdown
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MarkdownOptions {
+    /// How links are written \[Inline\]
+    pub link_style: LinkStyle,
+    /// Include images \[true\]
+    pub images: bool,
+    /// Element to render instead of \<body\>: a tag name (e.g. `article`) or `#` and an id; the
+    /// first match is used \[None\]
+    pub element: Option<String>,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            link_style: LinkStyle::Inline,
+            images: true,
+            element: None,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// Render the body of a document, or the element given in the options, as Markdown
+    ///
+    /// URLs are resolved like those of `HTML.links`: against `<base href>`, the canonical URL or
+    /// else the given URL.
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::{LinkStyle, MarkdownOptions};
+    ///
+    /// let input = r#"<h1>Title</h1><p>Some <em>text</em> with <a href="/about">a link</a>.</p>"#;
+    /// let markdown = MarkdownOptions::default().render(input, Some("https://example.org/"));
+    /// assert_eq!(
+    ///     markdown,
+    ///     "# Title\n\nSome *text* with [a link](https://example.org/about)."
+    /// );
+    ///
+    /// let input = r#"<nav>Menu</nav><main><p><a href="https://example.org">Link</a></p></main>"#;
+    /// let mut options = MarkdownOptions::default();
+    /// options.link_style = LinkStyle::Reference;
+    /// options.element = Some("main".to_string());
+    /// assert_eq!(
+    ///     options.render(input, None),
+    ///     "[Link][1]\n\n[1]: https://example.org"
+    /// );
+    /// ```
+    pub fn render(&self, input: &str, url: Option<&str>) -> String {
+        let document = Document::parse(input);
+        let html = HTML::from_document(&document, url.map(String::from), &mut []);
+        let root = match self.element.as_deref() {
+            Some(element) => match element.strip_prefix('#') {
+                Some(id) => find(&document, |node| document.attribute(node, "id") == Some(id)),
+                None => find(&document, |node| document.tag_name(node) == Some(element)),
+            },
+            None => find(&document, |node| document.tag_name(node) == Some("body")),
+        };
+
+        let mut writer = Writer {
+            document: &document,
+            options: self,
+            base: parser::base(&html),
+            buffers: vec![String::new()],
+            lists: Vec::new(),
+            tables: Vec::new(),
+            references: Vec::new(),
+            code: 0,
+        };
+        if let Some(root) = root {
+            writer.write(root);
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Webpage {
+    /// Render the body of this page as Markdown
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Webpage, WebpageOptions};
+    ///
+    /// let webpage = Webpage::from_url("https://example.org", WebpageOptions::default()).unwrap();
+    /// println!("{}", webpage.to_markdown());
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        self.to_markdown_with(&MarkdownOptions::default())
+    }
+
+    /// Render the body of this page, or the element given in the options, as Markdown
+    pub fn to_markdown_with(&self, options: &MarkdownOptions) -> String {
+        options.render(&self.http.body, Some(&self.http.url))
+    }
+}
+
+enum Step {
+    Enter(NodeId),
+    Exit(NodeId),
+}
+
+struct List {
+    ordered: bool,
+    /// Number of the next item, for ordered lists
+    number: u64,
+}
+
+/// Rows of cells of a table being written
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<String>>,
+}
+
+struct Writer<'a> {
+    document: &'a Document,
+    options: &'a MarkdownOptions,
+    base: Option<&'a Url>,
+    /// Output, and the contents of the open list items, block quotes, cells and inline elements
+    buffers: Vec<String>,
+    lists: Vec<List>,
+    tables: Vec<Table>,
+    /// URLs of reference style links
+    references: Vec<String>,
+    /// Depth of \<code\> elements, their text is not escaped
+    code: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn write(&mut self, root: NodeId) {
+        let document = self.document;
+        let mut stack = vec![Step::Enter(root)];
+
+        while let Some(step) = stack.pop() {
+            let id = match step {
+                Step::Enter(id) => id,
+                Step::Exit(id) => {
+                    self.exit(id);
+                    continue;
+                }
+            };
+            match document.node(id).data {
+                NodeData::Text(ref contents) => self.text(contents),
+                NodeData::Element { .. } if self.enter(id) => {
+                    stack.push(Step::Exit(id));
+                    // push in reverse, so the first child is written first
+                    let children_start = stack.len();
+                    stack.extend(document.children(id).map(Step::Enter));
+                    stack[children_start..].reverse();
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Start an element, returns whether its children should be written
+    fn enter(&mut self, id: NodeId) -> bool {
+        let tag_name = self.document.tag_name(id).unwrap_or_default();
+        if SKIP_TAGS.contains(&tag_name) || self.document.attribute(id, "hidden").is_some() {
+            return false;
+        }
+
+        match tag_name {
+            "a" | "em" | "i" | "strong" | "b" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+            | "blockquote" | "td" | "th" => self.buffers.push(String::new()),
+            "code" => {
+                self.code += 1;
+                self.buffers.push(String::new());
+            }
+            "li" => {
+                self.newline();
+                self.buffers.push(String::new());
+            }
+            "ul" | "ol" => {
+                if self.lists.is_empty() {
+                    self.blank_line();
+                } else {
+                    self.newline();
+                }
+                let number = self.document.attribute(id, "start");
+                self.lists.push(List {
+                    ordered: tag_name == "ol",
+                    number: number.and_then(|n| n.trim().parse().ok()).unwrap_or(1),
+                });
+            }
+            "table" => {
+                self.blank_line();
+                self.tables.push(Table::default());
+            }
+            "tr" => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            "pre" => {
+                self.code_block(id);
+                return false;
+            }
+            "br" => {
+                let buffer = self.buffer();
+                let trimmed = buffer.trim_end_matches(' ').len();
+                buffer.truncate(trimmed);
+                buffer.push_str("\\\n");
+                return false;
+            }
+            "hr" => {
+                self.blank_line();
+                self.buffer().push_str("---");
+                self.blank_line();
+                return false;
+            }
+            "img" => {
+                self.image(id);
+                return false;
+            }
+            tag_name if !INLINE_TAGS.contains(&tag_name) => self.blank_line(),
+            _ => (),
+        }
+        true
+    }
+
+    fn exit(&mut self, id: NodeId) {
+        let tag_name = self.document.tag_name(id).unwrap_or_default();
+        match tag_name {
+            "em" | "i" => self.wrap("*", "*"),
+            "strong" | "b" => self.wrap("**", "**"),
+            "code" => {
+                self.code = self.code.saturating_sub(1);
+                let contents = self.buffers.pop().unwrap_or_default();
+                let fence = "`".repeat(longest_run(&contents, '`') + 1);
+                // a backtick next to the fence would be read as part of it
+                let (open, close) = match contents.trim() {
+                    code if code.starts_with('`') || code.ends_with('`') => {
+                        (format!("{} ", fence), format!(" {}", fence))
+                    }
+                    _ => (fence.clone(), fence),
+                };
+                self.buffers.push(contents);
+                self.wrap(&open, &close);
+            }
+            "a" => self.link(id),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let contents = self.buffers.pop().unwrap_or_default();
+                let contents = single_line(&contents);
+                self.blank_line();
+                if !contents.is_empty() {
+                    let level = tag_name[1..].parse().unwrap_or(1);
+                    let buffer = self.buffer();
+                    buffer.push_str(&"#".repeat(level));
+                    buffer.push(' ');
+                    buffer.push_str(&contents);
+                }
+                self.blank_line();
+            }
+            "blockquote" => {
+                let contents = self.buffers.pop().unwrap_or_default();
+                self.blank_line();
+                let quoted: Vec<String> = contents
+                    .trim()
+                    .lines()
+                    .map(|line| match line {
+                        "" => ">".to_string(),
+                        line => format!("> {}", line),
+                    })
+                    .collect();
+                self.buffer().push_str(&quoted.join("\n"));
+                self.blank_line();
+            }
+            "li" => {
+                let contents = self.buffers.pop().unwrap_or_default();
+                let marker = match self.lists.last_mut() {
+                    Some(list) if list.ordered => {
+                        list.number += 1;
+                        format!("{}. ", list.number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                let indent = " ".repeat(marker.len());
+                let mut item = marker;
+                for (i, line) in contents.trim().lines().enumerate() {
+                    if i > 0 {
+                        item.push('\n');
+                        if !line.is_empty() {
+                            item.push_str(&indent);
+                        }
+                    }
+                    item.push_str(line);
+                }
+                self.newline();
+                self.buffer().push_str(&item);
+                self.newline();
+            }
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                } else {
+                    self.newline();
+                }
+            }
+            "td" | "th" => {
+                let contents = self.buffers.pop().unwrap_or_default();
+                let cell = single_line(&contents).replace('|', "\\|");
+                if let Some(table) = self.tables.last_mut() {
+                    match table.rows.last_mut() {
+                        Some(row) => row.push(cell),
+                        None => table.rows.push(vec![cell]),
+                    }
+                }
+            }
+            "table" => {
+                let table = self.tables.pop().unwrap_or_default();
+                self.table(table);
+            }
+            tag_name if !INLINE_TAGS.contains(&tag_name) => self.blank_line(),
+            _ => (),
+        }
+    }
+
+    fn buffer(&mut self) -> &mut String {
+        if self.buffers.is_empty() {
+            self.buffers.push(String::new());
+        }
+        self.buffers.last_mut().unwrap()
+    }
+
+    /// Append text with whitespace collapsed, escaping Markdown syntax
+    fn text(&mut self, contents: &str) {
+        let escape = self.code == 0;
+        let buffer = self.buffer();
+        let mut space = false;
+        for c in contents.chars() {
+            if c.is_whitespace() {
+                space = true;
+                continue;
+            }
+            let line_start = buffer.is_empty() || buffer.ends_with('\n');
+            if space && !line_start && !buffer.ends_with(' ') {
+                buffer.push(' ');
+            }
+            space = false;
+
+            let line_start = buffer.is_empty() || buffer.ends_with('\n');
+            if escape
+                && ("\\`*_[]<".contains(c)
+                    || (line_start && "#>-+=|".contains(c))
+                    || ((c == '.' || c == ')') && after_number(buffer)))
+            {
+                buffer.push('\\');
+            }
+            buffer.push(c);
+        }
+        let line_start = buffer.is_empty() || buffer.ends_with('\n');
+        if space && !line_start && !buffer.ends_with(' ') {
+            buffer.push(' ');
+        }
+    }
+
+    /// End the current line
+    fn newline(&mut self) {
+        self.separate(1);
+    }
+
+    /// End the current paragraph
+    fn blank_line(&mut self) {
+        self.separate(2);
+    }
+
+    fn separate(&mut self, newlines: usize) {
+        let buffer = self.buffer();
+        let trimmed = buffer.trim_end_matches(' ').len();
+        buffer.truncate(trimmed);
+        if buffer.is_empty() {
+            return;
+        }
+        let present = buffer.len() - buffer.trim_end_matches('\n').len();
+        for _ in present..newlines {
+            buffer.push('\n');
+        }
+    }
+
+    /// Close an inline element, keeping surrounding whitespace outside of the markers
+    fn wrap(&mut self, open: &str, close: &str) {
+        let contents = self.buffers.pop().unwrap_or_default();
+        let trimmed = contents.trim();
+        let buffer = self.buffer();
+        if contents.starts_with(' ') && !buffer.is_empty() && !buffer.ends_with([' ', '\n']) {
+            buffer.push(' ');
+        }
+        if !trimmed.is_empty() {
+            buffer.push_str(open);
+            buffer.push_str(trimmed);
+            buffer.push_str(close);
+        }
+        if contents.ends_with(' ') && !trimmed.is_empty() {
+            buffer.push(' ');
+        }
+    }
+
+    fn link(&mut self, id: NodeId) {
+        let href = self.document.attribute(id, "href").map(str::trim);
+        let href = match href.filter(|href| !href.is_empty() && !href.starts_with("javascript:")) {
+            Some(href) => parser::resolve(self.base, href),
+            None => return self.wrap("", ""),
+        };
+        let target = match self.options.link_style {
+            LinkStyle::Inline => match self.document.attribute(id, "title") {
+                Some(title) => format!(
+                    "({} \"{}\")",
+                    destination(&href),
+                    title.replace('"', "\\\"")
+                ),
+                None => format!("({})", destination(&href)),
+            },
+            LinkStyle::Reference => {
+                let number = match self.references.iter().position(|url| *url == href) {
+                    Some(index) => index + 1,
+                    None => {
+                        self.references.push(href.clone());
+                        self.references.len()
+                    }
+                };
+                format!("[{}]", number)
+            }
+        };
+        let contents = self.buffers.last_mut();
+        if let Some(contents) = contents.filter(|contents| contents.trim().is_empty()) {
+            // keep links without text visible
+            contents.push_str(&href.replace('[', "\\[").replace(']', "\\]"));
+        }
+        self.wrap("[", &format!("]{}", target));
+    }
+
+    fn image(&mut self, id: NodeId) {
+        if !self.options.images {
+            return;
+        }
+        let src = ["src", "data-src", "data-lazy-src", "data-original"]
+            .iter()
+            .filter_map(|name| self.document.attribute(id, name))
+            .map(str::trim)
+            .find(|src| !src.is_empty());
+        let src = match src {
+            Some(src) => parser::resolve(self.base, src),
+            None => return,
+        };
+        let alt = self.document.attribute(id, "alt").unwrap_or_default();
+        let alt = single_line(alt).replace('[', "\\[").replace(']', "\\]");
+        let image = match self.document.attribute(id, "title") {
+            Some(title) => format!(
+                "![{}]({} \"{}\")",
+                alt,
+                destination(&src),
+                title.replace('"', "\\\"")
+            ),
+            None => format!("![{}]({})", alt, destination(&src)),
+        };
+        let buffer = self.buffer();
+        buffer.push_str(&image);
+    }
+
+    fn code_block(&mut self, id: NodeId) {
+        let document = self.document;
+        let mut code = String::new();
+        let mut language = None;
+
+        let mut stack = vec![id];
+        while let Some(node) = stack.pop() {
+            match document.node(node).data {
+                NodeData::Text(ref contents) => code.push_str(contents),
+                NodeData::Element { .. } => {
+                    if document.tag_name(node) == Some("br") {
+                        code.push('\n');
+                    }
+                    let class = document.attribute(node, "class").unwrap_or_default();
+                    language = language.or_else(|| {
+                        class.split_whitespace().find_map(|class| {
+                            class
+                                .strip_prefix("language-")
+                                .or_else(|| class.strip_prefix("lang-"))
+                        })
+                    });
+                    let children_start = stack.len();
+                    stack.extend(document.children(node));
+                    stack[children_start..].reverse();
+                }
+                _ => (),
+            }
+        }
+
+        let fence = "`".repeat(longest_run(&code, '`').max(2) + 1);
+        let code = code.strip_suffix('\n').unwrap_or(&code);
+        self.blank_line();
+        let buffer = self.buffer();
+        buffer.push_str(&fence);
+        buffer.push_str(language.unwrap_or_default());
+        buffer.push('\n');
+        buffer.push_str(code);
+        buffer.push('\n');
+        buffer.push_str(&fence);
+        self.blank_line();
+    }
+
+    fn table(&mut self, table: Table) {
+        let rows: Vec<Vec<String>> = table
+            .rows
+            .into_iter()
+            .filter(|row| !row.is_empty())
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+
+        let mut lines = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let cells = (0..columns).map(|c| row.get(c).map_or("", |cell| &cell[..]));
+            lines.push(format!("| {} |", cells.collect::<Vec<_>>().join(" | ")));
+            if i == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+
+        self.blank_line();
+        self.buffer().push_str(&lines.join("\n"));
+        self.blank_line();
+    }
+
+    fn finish(mut self) -> String {
+        // close elements the traversal did not get to
+        while self.buffers.len() > 1 {
+            let contents = self.buffers.pop().unwrap_or_default();
+            self.buffer().push_str(&contents);
+        }
+        let mut output = self.buffers.pop().unwrap_or_default().trim().to_string();
+        if !self.references.is_empty() {
+            output.push_str("\n\n");
+            let references: Vec<String> = self
+                .references
+                .iter()
+                .enumerate()
+                .map(|(i, url)| format!("[{}]: {}", i + 1, destination(url)))
+                .collect();
+            output.push_str(&references.join("\n"));
+        }
+        output
+    }
+}
+
+/// First element matching the predicate, in document order
+fn find(document: &Document, predicate: impl Fn(NodeId) -> bool) -> Option<NodeId> {
+    let mut stack = vec![Document::ROOT];
+    while let Some(id) = stack.pop() {
+        if document.tag_name(id).is_some() && predicate(id) {
+            return Some(id);
+        }
+        let children_start = stack.len();
+        stack.extend(document.children(id));
+        stack[children_start..].reverse();
+    }
+    None
+}
+
+/// Link destination, in angle brackets when it contains spaces or parentheses
+fn destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+/// Collapse line breaks, for headings and table cells
+fn single_line(text: &str) -> String {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim().trim_end_matches('\\'))
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines.join(" ")
+}
+
+/// Whether the current line is only a number, which an ordered list marker would continue
+fn after_number(buffer: &str) -> bool {
+    let line = buffer.rsplit('\n').next().unwrap_or_default();
+    (1..=9).contains(&line.len()) && line.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(input: &str, options: &MarkdownOptions) -> String {
+        options.render(input, Some("https://example.org/blog/"))
+    }
+
+    #[test]
+    fn test_blocks() {
+        let input = r#"<body><h2>Intro <small>(draft)</small></h2>
+            <p>One <strong>bold </strong>and <code>a `tick`</code> with 2 * 3_</p>
+            <ul><li>First</li><li>Second<ol start="3"><li>Nested</li></ol></li></ul>
+            <blockquote><p>Quote</p><p>More</p></blockquote>
+            <pre class="language-rust">fn main() {
+    println!("hi");
+}
+</pre>
+            <table><tr><th>Name</th><th>Value</th></tr><tr><td>a|b</td></tr></table>
+            <p>Line<br>break</p><hr><script>ignored()</script>"#;
+        let expected = r#"## Intro (draft)
+
+One **bold** and `` a `tick` `` with 2 \* 3\_
+
+- First
+- Second
+  3. Nested
+
+> Quote
+>
+> More
+
+```rust
+fn main() {
+    println!("hi");
+}
+```
+
+| Name | Value |
+| --- | --- |
+| a\|b |  |
+
+Line\
+break
+
+---"#;
+        assert_eq!(markdown(input, &MarkdownOptions::default()), expected);
+    }
+
+    #[test]
+    fn test_links_and_images() {
+        let input = r#"<p><a href="post" title="A post">Read</a> <a href="/x">X</a> <a href="post">again</a>
+            <img src="../a b.png" alt="Alt [1]"></p>"#;
+        assert_eq!(
+            markdown(input, &MarkdownOptions::default()),
+            "[Read](https://example.org/blog/post \"A post\") [X](https://example.org/x) \
+             [again](https://example.org/blog/post) ![Alt \\[1\\]](https://example.org/a%20b.png)"
+        );
+
+        let mut options = MarkdownOptions {
+            link_style: LinkStyle::Reference,
+            images: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            markdown(input, &options),
+            "[Read][1] [X][2] [again][1]\n\n\
+             [1]: https://example.org/blog/post\n[2]: https://example.org/x"
+        );
+
+        options.element = Some("#missing".to_string());
+        assert_eq!(markdown(input, &options), "");
+    }
+
+    #[test]
+    fn test_base_and_numbers() {
+        let input = r#"<head><base href="/static/"></head>
+            <p>2024. A year</p><p>1) Not <a href="a">a list</a> <img src="i.png" alt=""></p>
+            <p>In 2024. Fine</p>"#;
+        let html = HTML::from_string(input.to_string(), Some("https://example.org/".into()));
+        assert_eq!(html.unwrap().links[0].url, "https://example.org/static/a");
+        assert_eq!(
+            markdown(input, &MarkdownOptions::default()),
+            "2024\\. A year\n\n1\\) Not [a list](https://example.org/static/a) \
+             ![](https://example.org/static/i.png)\n\nIn 2024. Fine"
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! oEmbed discovery and responses, see <https://oembed.com>
+
+use std::io;
+
+use serde_json::Value;
+
+use crate::xml;
+#[cfg(feature = "curl")]
+use crate::{Client, Request, Webpage, WebpageOptions};
+
+/// Format of an oEmbed endpoint
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OEmbedFormat {
+    Json,
+    Xml,
+}
+
+/// oEmbed endpoint advertised by `<link rel="alternate" type="application/json+oembed">`
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct OEmbedEndpoint {
+    /// Resolved URL of the endpoint, including the `url` query parameter
+    pub url: String,
+    pub format: OEmbedFormat,
+    pub title: Option<String>,
+}
+
+impl OEmbedEndpoint {
+    /// Endpoint for a link element with the given content type, if it is an oEmbed type
+    pub(crate) fn from_link(link_type: &str, url: String, title: Option<String>) -> Option<Self> {
+        let format = match &link_type.to_ascii_lowercase()[..] {
+            "application/json+oembed" => OEmbedFormat::Json,
+            "text/xml+oembed" | "application/xml+oembed" => OEmbedFormat::Xml,
+            _ => return None,
+        };
+        Some(Self { url, format, title })
+    }
+}
+
+/// oEmbed response
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct OEmbed {
+    /// Resource type: photo, video, link or rich
+    pub oembed_type: String,
+    pub version: Option<String>,
+    pub title: Option<String>,
+    pub author_name: Option<String>,
+    pub author_url: Option<String>,
+    pub provider_name: Option<String>,
+    pub provider_url: Option<String>,
+    /// Suggested cache lifetime in seconds
+    pub cache_age: Option<u64>,
+    pub thumbnail_url: Option<String>,
+    pub thumbnail_width: Option<u32>,
+    pub thumbnail_height: Option<u32>,
+    /// Image URL, for the photo type
+    pub url: Option<String>,
+    /// HTML to embed, for the video and rich types
+    pub html: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl OEmbed {
+    /// Parse a JSON or XML oEmbed response
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::OEmbed;
+    ///
+    /// let input = r#"{"type": "video", "version": "1.0", "html": "<iframe></iframe>", "width": 480}"#;
+    /// let oembed = OEmbed::parse(input.as_bytes()).unwrap();
+    /// assert_eq!(oembed.oembed_type, "video");
+    /// assert_eq!(oembed.width, Some(480));
+    /// ```
+    pub fn parse(input: &[u8]) -> Result<Self, io::Error> {
+        let start = input.iter().find(|b| !b.is_ascii_whitespace());
+        let fields: Vec<(String, String)> = if start == Some(&b'<') {
+            let document = xml::parse(input)?;
+            xml::children(&document.root)
+                .iter()
+                .map(|child| (xml::name(child).to_string(), xml::text(child)))
+                .collect()
+        } else {
+            let value: Value = serde_json::from_slice(input)?;
+            value
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter_map(|(key, value)| {
+                    let value = match value {
+                        Value::String(string) => string.clone(),
+                        Value::Number(number) => number.to_string(),
+                        _ => return None,
+                    };
+                    Some((key.clone(), value))
+                })
+                .collect()
+        };
+
+        let mut oembed = Self::default();
+        for (key, value) in fields {
+            let number = || value.parse::<f64>().ok().map(|n| n as u32);
+            match &key[..] {
+                "type" => oembed.oembed_type = value,
+                "version" => oembed.version = Some(value),
+                "title" => oembed.title = Some(value),
+                "author_name" => oembed.author_name = Some(value),
+                "author_url" => oembed.author_url = Some(value),
+                "provider_name" => oembed.provider_name = Some(value),
+                "provider_url" => oembed.provider_url = Some(value),
+                "cache_age" => oembed.cache_age = value.parse().ok(),
+                "thumbnail_url" => oembed.thumbnail_url = Some(value),
+                "thumbnail_width" => oembed.thumbnail_width = number(),
+                "thumbnail_height" => oembed.thumbnail_height = number(),
+                "url" => oembed.url = Some(value),
+                "html" => oembed.html = Some(value),
+                "width" => oembed.width = number(),
+                "height" => oembed.height = number(),
+                _ => (),
+            }
+        }
+
+        if oembed.oembed_type.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "oEmbed response without type",
+            ));
+        }
+        Ok(oembed)
+    }
+}
+
+#[cfg(feature = "curl")]
+impl OEmbed {
+    /// Fetch an oEmbed endpoint, asking for an embed of at most the given dimensions
+    pub fn fetch(
+        endpoint: &str,
+        maxwidth: Option<u32>,
+        maxheight: Option<u32>,
+        options: WebpageOptions,
+    ) -> Result<Self, io::Error> {
+        Client::new(options).fetch_oembed(endpoint, maxwidth, maxheight)
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Client {
+    /// Fetch an oEmbed endpoint, asking for an embed of at most the given dimensions
+    pub fn fetch_oembed(
+        &self,
+        endpoint: &str,
+        maxwidth: Option<u32>,
+        maxheight: Option<u32>,
+    ) -> Result<OEmbed, io::Error> {
+        let mut url = url::Url::parse(endpoint)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| key != "maxwidth" && key != "maxheight")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        {
+            let mut query = url.query_pairs_mut();
+            query.clear().extend_pairs(pairs);
+            if let Some(maxwidth) = maxwidth {
+                query.append_pair("maxwidth", &maxwidth.to_string());
+            }
+            if let Some(maxheight) = maxheight {
+                query.append_pair("maxheight", &maxheight.to_string());
+            }
+        }
+
+        let (http, body) = self.send_raw(Request::get(url.as_str()))?;
+        if !(200..300).contains(&http.response_code) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("HTTP status {} for oEmbed {}", http.response_code, url),
+            ));
+        }
+        OEmbed::parse(&body)
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Webpage {
+    /// Fetch the oEmbed response for this page, if it advertises an endpoint (JSON preferred)
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Webpage, WebpageOptions};
+    ///
+    /// let url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+    /// let webpage = Webpage::from_url(url, WebpageOptions::default()).unwrap();
+    /// if let Some(Ok(oembed)) = webpage.fetch_oembed(Some(640), None, WebpageOptions::default()) {
+    ///     println!("{:?}", oembed.html);
+    /// }
+    /// ```
+    pub fn fetch_oembed(
+        &self,
+        maxwidth: Option<u32>,
+        maxheight: Option<u32>,
+        options: WebpageOptions,
+    ) -> Option<Result<OEmbed, io::Error>> {
+        let endpoints = &self.html.oembed;
+        let endpoint = endpoints
+            .iter()
+            .find(|endpoint| endpoint.format == OEmbedFormat::Json)
+            .or_else(|| endpoints.first())?;
+        Some(OEmbed::fetch(&endpoint.url, maxwidth, maxheight, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let input = r#"{
+            "type": "photo",
+            "version": "1.0",
+            "url": "https://example.org/photo.jpg",
+            "width": "640",
+            "height": 480.0,
+            "provider_name": "Example",
+            "cache_age": 3600,
+            "thumbnail_url": "https://example.org/thumb.jpg",
+            "thumbnail_width": 100
+        }"#;
+        let oembed = OEmbed::parse(input.as_bytes()).unwrap();
+        assert_eq!(oembed.oembed_type, "photo");
+        assert_eq!(oembed.width, Some(640));
+        assert_eq!(oembed.height, Some(480));
+        assert_eq!(oembed.provider_name, Some("Example".to_string()));
+        assert_eq!(oembed.cache_age, Some(3600));
+        assert_eq!(oembed.thumbnail_width, Some(100));
+
+        assert!(OEmbed::parse(b"{\"version\": \"1.0\"}").is_err());
+    }
+
+    #[test]
+    fn test_parse_xml() {
+        let input = r#"<?xml version="1.0" encoding="utf-8" standalone="yes"?>
+<oembed>
+  <version>1.0</version>
+  <type>video</type>
+  <html>&lt;iframe src="https://example.org/embed"&gt;&lt;/iframe&gt;</html>
+  <width>480</width>
+  <height>270</height>
+</oembed>"#;
+        let oembed = OEmbed::parse(input.as_bytes()).unwrap();
+        assert_eq!(oembed.oembed_type, "video");
+        assert_eq!(
+            oembed.html,
+            Some("<iframe src=\"https://example.org/embed\"></iframe>".to_string())
+        );
+        assert_eq!(oembed.height, Some(270));
+    }
+}
@@ -1,51 +1,71 @@
-use html5ever::tendril::{fmt::UTF8, Tendril};
 use html5ever::Attribute;
-use markup5ever_rcdom::{Handle, NodeData};
+use url::Url;
 
+use crate::extractor::{Element, Extractor, Segment, Text};
+use crate::heading::{self, Heading};
 use crate::html::{Link, HTML};
+use crate::icon::Icon;
+use crate::image::{Image, ImageElement};
+use crate::oembed::OEmbedEndpoint;
 use crate::schema_org::SchemaOrg;
+use crate::sink::{Document, NodeData, NodeId};
+use crate::text::TextWriter;
 
-#[derive(Copy, Clone)]
-enum Segment {
-    None,
-    Head,
-    Body,
-}
+/// Content types of `<link rel="alternate">` elements pointing to a feed
+pub(crate) const FEED_TYPES: [&str; 6] = [
+    "application/atom+xml",
+    "application/json",
+    "application/rdf+xml",
+    "application/rss+xml",
+    "application/xml",
+    "text/xml",
+];
 
-pub struct Parser<'a> {
-    segment: Segment,
-    parent: Option<&'a NodeData>,
-    handle: Handle,
+enum Step {
+    Enter(NodeId),
+    Exit(NodeId),
 }
 
-impl<'a> Parser<'a> {
-    pub fn start(handle: Handle) -> Self {
-        Parser {
-            handle,
-            segment: Segment::None,
-            parent: None,
-        }
-    }
+/// Walk the document in document order, fill in the HTML info and run the custom extractors
+///
+/// Uses an explicit stack instead of recursion, so nesting depth is not limited by the call stack.
+pub(crate) fn extract(document: &Document, html: &mut HTML, extractors: &mut [&mut dyn Extractor]) {
+    // (step, segment, depth) and the tag names of the ancestors of the current node
+    let mut stack = vec![(Step::Enter(Document::ROOT), Segment::None, 0)];
+    let mut ancestors: Vec<&str> = Vec::new();
+    let mut text_content = TextWriter::default();
 
-    pub fn traverse(self, html: &mut HTML) {
-        let mut segment = self.segment;
-
-        let handle_ref = &self.handle;
-        match self.handle.data {
-            NodeData::Document => (),
-            NodeData::Doctype { .. } => (),
-            NodeData::Comment { .. } => (),
-
-            NodeData::Text { ref contents } => {
-                if let Some(NodeData::Element { ref name, .. }) = self.parent {
-                    let tag_name = name.local.as_ref();
-
-                    process_text(
-                        self.segment,
-                        tag_name,
-                        tendril_to_utf8(&contents.borrow()),
-                        html,
-                    )
+    while let Some((step, mut segment, depth)) = stack.pop() {
+        let id = match step {
+            Step::Enter(id) => id,
+            Step::Exit(id) => {
+                if segment == Segment::Body {
+                    text_content.end(document.tag_name(id).unwrap_or_default());
+                }
+                continue;
+            }
+        };
+        ancestors.truncate(depth);
+
+        let node = document.node(id);
+        match node.data {
+            NodeData::Document | NodeData::Other => (),
+
+            NodeData::Text(ref contents) => {
+                if let Some(tag_name) = document
+                    .parent(id)
+                    .and_then(|parent| document.tag_name(parent))
+                {
+                    process_text(segment, tag_name, contents, &mut text_content);
+
+                    let text = Text {
+                        contents,
+                        segment,
+                        ancestors: &ancestors,
+                    };
+                    for extractor in extractors.iter_mut() {
+                        extractor.text(&text, html);
+                    }
                 }
             }
 
@@ -62,30 +82,39 @@ impl<'a> Parser<'a> {
                     segment = Segment::Body;
                 }
 
-                process_element(segment, tag_name, handle_ref, &attrs.borrow(), html)
-            }
+                process_element(segment, tag_name, document, id, attrs, html);
+                if segment == Segment::Body {
+                    text_content.start(tag_name);
+                }
 
-            NodeData::ProcessingInstruction { .. } => unreachable!(),
+                let element = Element {
+                    tag_name,
+                    attrs,
+                    segment,
+                    ancestors: &ancestors,
+                };
+                for extractor in extractors.iter_mut() {
+                    extractor.element(&element, html);
+                }
+                ancestors.push(tag_name);
+                stack.push((Step::Exit(id), segment, depth));
+            }
         }
 
-        for child in self.handle.children.borrow().iter() {
-            let new_parser = Parser {
-                segment,
-                parent: Some(&self.handle.data),
-                handle: child.clone(),
-            };
-            new_parser.traverse(html);
-        }
+        // push in reverse, so the first child is processed first
+        let children_start = stack.len();
+        let children = document.children(id);
+        stack.extend(children.map(|child| (Step::Enter(child), segment, ancestors.len())));
+        stack[children_start..].reverse();
     }
+
+    html.text_content = text_content.finish();
 }
 
-fn process_text(segment: Segment, tag_name: &str, contents: &str, html: &mut HTML) {
+fn process_text(segment: Segment, tag_name: &str, contents: &str, text_content: &mut TextWriter) {
     if let Segment::Body = segment {
         if tag_name != "style" && tag_name != "script" && tag_name != "noscript" {
-            if !html.text_content.is_empty() {
-                html.text_content.push(' ');
-            }
-            html.text_content.push_str(contents);
+            text_content.push(contents);
         }
     }
 }
@@ -93,7 +122,8 @@ fn process_text(segment: Segment, tag_name: &str, contents: &str, html: &mut HTM
 fn process_element(
     segment: Segment,
     tag_name: &str,
-    handle: &Handle,
+    document: &Document,
+    id: NodeId,
     attrs: &[Attribute],
     html: &mut HTML,
 ) {
@@ -108,7 +138,7 @@ fn process_element(
     // process <head>
     if let Segment::Head = segment {
         if tag_name == "title" {
-            html.title = text_content(handle);
+            html.title = text_content(document, id);
         }
         if tag_name == "meta" {
             let content = get_attribute(attrs, "content");
@@ -122,8 +152,12 @@ fn process_element(
 
                     if property.starts_with("og:") && property.len() > 3 {
                         html.opengraph.extend(&property[3..], content);
+                    } else if property.starts_with("twitter:") && property.len() > 8 {
+                        html.twitter_card.extend(&property[8..], content);
                     } else if property == "description" {
                         html.description = Some(content);
+                    } else if get_attribute(attrs, "name").is_some() {
+                        html.robots.add_meta(&property, &content);
                     }
                 }
             }
@@ -132,33 +166,87 @@ fn process_element(
                 html.meta.insert("charset".to_string(), charset);
             }
         }
+        if tag_name == "base" && html.base_url.is_none() {
+            if let Some(href) = get_attribute(attrs, "href") {
+                html.base_url = match &html.url_parsed {
+                    Some(url) => url.join(&href).ok(),
+                    None => Url::parse(&href).ok(),
+                };
+            }
+        }
         if tag_name == "link" {
             let rel = get_attribute(attrs, "rel").unwrap_or_default();
-            if rel == "canonical" {
+            // rel is a space separated list of case insensitive link types
+            let has_rel = |link_type: &str| {
+                rel.split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case(link_type))
+            };
+            if has_rel("canonical") {
                 html.set_url(get_attribute(attrs, "href"));
-            } else if rel == "alternate" {
+            } else if has_rel("alternate") {
                 let link_type = get_attribute(attrs, "type").unwrap_or_default();
-                if [
-                    "application/atom+xml",
-                    "application/json",
-                    "application/rdf+xml",
-                    "application/rss+xml",
-                    "application/xml",
-                    "text/xml",
-                ]
-                .contains(&&link_type[..])
-                {
+                if FEED_TYPES.contains(&&link_type[..]) {
                     html.feed = get_attribute(attrs, "href");
+                } else if let Some(href) = get_attribute(attrs, "href") {
+                    let url = resolve(base(html), &href);
+                    let title = get_attribute(attrs, "title");
+                    html.oembed
+                        .extend(OEmbedEndpoint::from_link(&link_type, url, title));
                 }
+            } else if has_rel("next") {
+                html.next = get_attribute(attrs, "href").map(|href| resolve(base(html), &href));
+            } else if has_rel("prev") || has_rel("previous") {
+                html.prev = get_attribute(attrs, "href").map(|href| resolve(base(html), &href));
+            } else if has_rel("manifest") {
+                html.manifest = get_attribute(attrs, "href").map(|href| resolve(base(html), &href));
+            }
+            // an icon may have other link types too, e.g. `rel="alternate icon"`
+            if let Some(href) = get_attribute(attrs, "href") {
+                let url = resolve(base(html), &href);
+                let sizes = get_attribute(attrs, "sizes");
+                let mime_type = get_attribute(attrs, "type");
+                let color = get_attribute(attrs, "color");
+                html.icons.extend(Icon::from_link(
+                    &rel,
+                    url,
+                    sizes.as_deref(),
+                    mime_type,
+                    color,
+                ));
             }
         }
     }
 
+    // process <body>
+    if let Segment::Body = segment {
+        if let Some(level) = heading::level(tag_name) {
+            let anchor = get_attribute(attrs, "id");
+            let heading = Heading::new(level, document.text(id), anchor, html.url_parsed.as_ref());
+            html.headings.push(heading);
+        }
+    }
+
+    // process images
+    let parent_tag = document
+        .parent(id)
+        .and_then(|parent| document.tag_name(parent));
+    let input_type = || get_attribute(attrs, "type").unwrap_or_default();
+    let image_element = match tag_name {
+        "img" => Some(ImageElement::Img),
+        "source" if parent_tag == Some("picture") => Some(ImageElement::Source),
+        "input" if input_type().eq_ignore_ascii_case("image") => Some(ImageElement::Input),
+        _ => None,
+    };
+    if let Some(element) = image_element {
+        let attribute = |name: &str| get_attribute(attrs, name);
+        html.images.push(Image::new(element, attribute, base(html)));
+    }
+
     // process ld-json snippets
     if tag_name == "script" {
         if let Some(script_type) = get_attribute(attrs, "type") {
             if script_type == "application/ld+json" {
-                if let Some(content) = text_content(handle) {
+                if let Some(content) = text_content(document, id) {
                     html.schema_org.append(&mut SchemaOrg::from(content));
                 }
             }
@@ -167,21 +255,26 @@ fn process_element(
 
     if tag_name == "a" {
         if let Some(href) = get_attribute(attrs, "href") {
-            let text = text_content(handle).unwrap_or_default();
-            let href = if let Some(url) = &html.url_parsed {
-                if let Ok(url) = url.join(&href) {
-                    url.to_string()
-                } else {
-                    href
-                }
-            } else {
-                href
-            };
+            let text = text_content(document, id).unwrap_or_default();
+            let href = resolve(base(html), &href);
             html.links.push(Link { url: href, text });
         }
     }
 }
 
+/// URL relative URLs resolve against: \<base href\>, else the URL of the document
+pub(crate) fn base(html: &HTML) -> Option<&Url> {
+    html.base_url.as_ref().or(html.url_parsed.as_ref())
+}
+
+/// Resolve a (relative) URL against the base URL, see [`base`], if known
+pub(crate) fn resolve(base: Option<&Url>, url: &str) -> String {
+    match base {
+        Some(base) => base.join(url).map_or(url.to_string(), String::from),
+        None => url.to_string(),
+    }
+}
+
 fn get_attribute(attrs: &[Attribute], name: &str) -> Option<String> {
     attrs
         .iter()
@@ -189,18 +282,7 @@ fn get_attribute(attrs: &[Attribute], name: &str) -> Option<String> {
         .map(|attr| attr.value.trim().to_string())
 }
 
-fn text_content(handle: &Handle) -> Option<String> {
+fn text_content(document: &Document, id: NodeId) -> Option<String> {
     // todo paste all the text together
-    for child in handle.children.borrow().iter() {
-        if let NodeData::Text { ref contents } = child.data {
-            let string = tendril_to_utf8(&contents.borrow()).to_string();
-            return Some(string.trim().to_string());
-        }
-    }
-
-    None
-}
-
-fn tendril_to_utf8(t: &Tendril<UTF8>) -> &str {
-    t
+    document.first_text(id).map(String::from)
 }
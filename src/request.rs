@@ -0,0 +1,146 @@
+//! Building HTTP requests beyond a plain GET
+
+use std::io;
+
+use url::form_urlencoded;
+
+use crate::{Client, WebpageOptions, HTTP};
+
+/// HTTP request method
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+}
+
+impl Method {
+    /// The method as it appears in the request line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+        }
+    }
+}
+
+/// Request to send, with an optional body
+///
+/// ## Examples
+/// ```no_run
+/// use webpage::{Request, WebpageOptions};
+///
+/// let http = Request::post("https://example.org/search")
+///     .form(&[("q", "rust")])
+///     .send(WebpageOptions::default())
+///     .expect("Could not send request");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub(crate) method: Method,
+    pub(crate) url: String,
+    pub(crate) headers: Vec<String>,
+    pub(crate) body: Option<Vec<u8>>,
+}
+
+impl Request {
+    /// Create a request with the given method
+    pub fn new(method: Method, url: &str) -> Self {
+        Self {
+            method,
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// Create a GET request
+    pub fn get(url: &str) -> Self {
+        Self::new(Method::Get, url)
+    }
+
+    /// Create a HEAD request, the response body will be empty
+    pub fn head(url: &str) -> Self {
+        Self::new(Method::Head, url)
+    }
+
+    /// Create a POST request, with an empty body unless one is set
+    pub fn post(url: &str) -> Self {
+        Self::new(Method::Post, url)
+    }
+
+    /// Add a HTTP header (`Name: value`), sent along with `WebpageOptions.headers`
+    pub fn header(mut self, header: &str) -> Self {
+        self.headers.push(header.to_string());
+        self
+    }
+
+    /// Set a raw body with its content type, replacing a Content-Type header set before
+    pub fn body(mut self, content_type: &str, body: Vec<u8>) -> Self {
+        self.headers.retain(|header| {
+            let name = header.split(':').next().unwrap_or_default();
+            !name.trim().eq_ignore_ascii_case("content-type")
+        });
+        let mut request = self.header(&format!("Content-Type: {}", content_type));
+        request.body = Some(body);
+        request
+    }
+
+    /// Set an `application/x-www-form-urlencoded` body
+    pub fn form(self, fields: &[(&str, &str)]) -> Self {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+        self.body("application/x-www-form-urlencoded", body.into_bytes())
+    }
+
+    /// Set an `application/json` body
+    pub fn json(self, value: &serde_json::Value) -> Self {
+        self.body("application/json", value.to_string().into_bytes())
+    }
+
+    /// Perform the request
+    pub fn send(self, options: WebpageOptions) -> Result<HTTP, io::Error> {
+        Client::new(options).send(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form() {
+        let request = Request::post("http://example.org").form(&[("q", "a b&c"), ("n", "1")]);
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(
+            request.headers,
+            vec!["Content-Type: application/x-www-form-urlencoded".to_string()]
+        );
+        assert_eq!(request.body, Some(b"q=a+b%26c&n=1".to_vec()));
+    }
+
+    #[test]
+    fn test_json() {
+        let request = Request::post("http://example.org").json(&serde_json::json!({"a": 1}));
+        assert_eq!(request.headers, vec!["Content-Type: application/json"]);
+        assert_eq!(request.body, Some(b"{\"a\":1}".to_vec()));
+    }
+
+    #[test]
+    fn test_body_replaces_content_type() {
+        let request = Request::post("http://example.org")
+            .header("content-type: text/plain")
+            .header("Accept: */*")
+            .json(&serde_json::json!(null))
+            .body("text/csv", b"a,b".to_vec());
+        assert_eq!(
+            request.headers,
+            vec!["Accept: */*", "Content-Type: text/csv"]
+        );
+        assert_eq!(request.body, Some(b"a,b".to_vec()));
+    }
+}
@@ -0,0 +1,317 @@
+//! Robots directives from `<meta name="robots">` and the `X-Robots-Tag` header
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use crate::date::{from_http_date, from_iso8601};
+
+/// Directives that take a value after a colon, these are not crawler names
+const VALUE_DIRECTIVES: [&str; 4] = [
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+    "unavailable_after",
+];
+
+/// Meta names addressing a specific crawler, besides those ending in "bot"
+const CRAWLERS: [&str; 8] = [
+    "googlebot-news",
+    "googlebot-image",
+    "googlebot-video",
+    "slurp",
+    "yandex",
+    "baiduspider",
+    "teoma",
+    "adsbot-google",
+];
+
+/// Parsed robots directives
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct RobotsDirectives {
+    /// Do not show the page in search results
+    pub noindex: bool,
+    /// Do not follow the links on the page
+    pub nofollow: bool,
+    /// Do not show a cached copy
+    pub noarchive: bool,
+    /// Do not show a text snippet or video preview
+    pub nosnippet: bool,
+    /// Do not index images on the page
+    pub noimageindex: bool,
+    /// Do not offer a translation
+    pub notranslate: bool,
+    /// Maximum snippet length in characters, -1 for no limit
+    pub max_snippet: Option<i64>,
+    /// Maximum image preview size: none, standard or large
+    pub max_image_preview: Option<String>,
+    /// Maximum video preview length in seconds, -1 for no limit
+    pub max_video_preview: Option<i64>,
+    /// Do not show the page in search results after this time
+    pub unavailable_after: Option<SystemTime>,
+}
+
+impl RobotsDirectives {
+    /// Parse a comma separated list of directives, such as `noindex, max-snippet:50`
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::RobotsDirectives;
+    ///
+    /// let directives = RobotsDirectives::parse("none, max-image-preview:large");
+    /// assert!(directives.noindex && directives.nofollow);
+    /// assert_eq!(directives.max_image_preview, Some("large".to_string()));
+    /// ```
+    pub fn parse(value: &str) -> Self {
+        let mut directives = Self::default();
+        directives.extend(value);
+        directives
+    }
+
+    fn extend(&mut self, value: &str) {
+        let parts: Vec<&str> = value.split(',').collect();
+        let mut i = 0;
+        while i < parts.len() {
+            let part = parts[i].trim();
+            i += 1;
+
+            let (name, argument) = match part.split_once(':') {
+                Some((name, argument)) => (name.trim().to_ascii_lowercase(), argument.trim()),
+                None => (part.to_ascii_lowercase(), ""),
+            };
+            match &name[..] {
+                "noindex" => self.noindex = true,
+                "nofollow" => self.nofollow = true,
+                "none" => {
+                    self.noindex = true;
+                    self.nofollow = true;
+                }
+                "noarchive" | "nocache" => self.noarchive = true,
+                "nosnippet" => self.nosnippet = true,
+                "noimageindex" => self.noimageindex = true,
+                "notranslate" => self.notranslate = true,
+                "max-snippet" => self.max_snippet = argument.parse().ok(),
+                "max-image-preview" => self.max_image_preview = Some(argument.to_ascii_lowercase()),
+                "max-video-preview" => self.max_video_preview = argument.parse().ok(),
+                "unavailable_after" => {
+                    // dates like "Friday, 25-Jun-10 15:00:00 GMT" contain a comma
+                    let mut date = argument.to_string();
+                    self.unavailable_after = parse_date(&date);
+                    if self.unavailable_after.is_none() && i < parts.len() {
+                        date.push(',');
+                        date.push_str(parts[i]);
+                        if let Some(time) = parse_date(&date) {
+                            self.unavailable_after = Some(time);
+                            i += 1;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Combine with other directives, keeping the most restrictive settings
+    pub fn merge(&mut self, other: &Self) {
+        self.noindex |= other.noindex;
+        self.nofollow |= other.nofollow;
+        self.noarchive |= other.noarchive;
+        self.nosnippet |= other.nosnippet;
+        self.noimageindex |= other.noimageindex;
+        self.notranslate |= other.notranslate;
+
+        let min_limit = |a: Option<i64>, b: Option<i64>| match (a, b) {
+            // -1 means no limit
+            (Some(a), Some(b)) if a < 0 => Some(b),
+            (Some(a), Some(b)) if b < 0 => Some(a),
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max_snippet = min_limit(self.max_snippet, other.max_snippet);
+        self.max_video_preview = min_limit(self.max_video_preview, other.max_video_preview);
+
+        let rank = |preview: &Option<String>| match preview.as_deref() {
+            Some("none") => 0,
+            Some("standard") => 1,
+            _ => 2,
+        };
+        if rank(&other.max_image_preview) < rank(&self.max_image_preview) {
+            self.max_image_preview = other.max_image_preview.clone();
+        }
+
+        self.unavailable_after = match (self.unavailable_after, other.unavailable_after) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Whether the page may be shown in search results at the given time
+    pub fn is_indexable_at(&self, now: SystemTime) -> bool {
+        !self.noindex && self.unavailable_after.map_or(true, |time| now < time)
+    }
+
+    /// Whether the page may be shown in search results
+    pub fn is_indexable(&self) -> bool {
+        self.is_indexable_at(SystemTime::now())
+    }
+
+    /// Whether links on the page may be followed
+    pub fn is_followable(&self) -> bool {
+        !self.nofollow
+    }
+}
+
+/// Robots directives for all crawlers, and for specific ones
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Robots {
+    /// Directives for all crawlers
+    pub all: RobotsDirectives,
+    /// Directives for a specific crawler, keyed by lower cased name (e.g. `googlebot`)
+    pub crawlers: HashMap<String, RobotsDirectives>,
+}
+
+impl Robots {
+    /// Parse the values of `X-Robots-Tag` headers, optionally prefixed with a crawler name
+    /// (`googlebot: noindex`)
+    pub fn from_header_values<'a>(values: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut robots = Self::default();
+        for value in values {
+            match value.split_once(':') {
+                Some((name, directives)) if is_crawler(name.trim()) => {
+                    robots.add(Some(name.trim()), directives)
+                }
+                _ => robots.add(None, value),
+            }
+        }
+        robots
+    }
+
+    /// Add directives for all crawlers (`None`) or a specific one
+    pub(crate) fn add(&mut self, crawler: Option<&str>, value: &str) {
+        match crawler {
+            Some(name) => self
+                .crawlers
+                .entry(name.to_ascii_lowercase())
+                .or_default()
+                .extend(value),
+            None => self.all.extend(value),
+        }
+    }
+
+    /// Handle a `<meta name=".." content="..">` element, ignoring non-robots names
+    pub(crate) fn add_meta(&mut self, name: &str, content: &str) {
+        let name = name.to_ascii_lowercase();
+        if name == "robots" {
+            self.add(None, content);
+        } else if name.ends_with("bot") || CRAWLERS.contains(&&name[..]) {
+            self.add(Some(&name), content);
+        }
+    }
+
+    /// Combine with directives from another source
+    pub fn merge(&mut self, other: &Self) {
+        self.all.merge(&other.all);
+        for (name, directives) in &other.crawlers {
+            self.crawlers
+                .entry(name.clone())
+                .or_default()
+                .merge(directives);
+        }
+    }
+
+    /// Effective directives for the given crawler: the general ones combined with its specific
+    /// ones
+    pub fn for_crawler(&self, name: &str) -> RobotsDirectives {
+        let mut directives = self.all.clone();
+        if let Some(specific) = self.crawlers.get(&name.to_ascii_lowercase()) {
+            directives.merge(specific);
+        }
+        directives
+    }
+}
+
+/// Whether the prefix before a colon names a crawler rather than a directive
+fn is_crawler(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains([',', ' '])
+        && !VALUE_DIRECTIVES
+            .iter()
+            .any(|directive| directive.eq_ignore_ascii_case(name))
+}
+
+fn parse_date(input: &str) -> Option<SystemTime> {
+    from_http_date(input).or_else(|| from_iso8601(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_directives() {
+        let directives = RobotsDirectives::parse(
+            "NOINDEX, max-snippet:-1, max-video-preview: 30, unavailable_after: 2024-02-29",
+        );
+        assert!(directives.noindex);
+        assert!(!directives.nofollow);
+        assert_eq!(directives.max_snippet, Some(-1));
+        assert_eq!(directives.max_video_preview, Some(30));
+        assert_eq!(
+            directives.unavailable_after,
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_164_800))
+        );
+
+        let directives = RobotsDirectives::parse(
+            "unavailable_after: Thursday, 29-Feb-24 00:00:00 GMT, noarchive",
+        );
+        assert_eq!(
+            directives.unavailable_after,
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_164_800))
+        );
+        assert!(directives.noarchive);
+        assert!(directives.is_followable());
+        assert!(!directives.is_indexable());
+    }
+
+    #[test]
+    fn test_invalid_unavailable_after() {
+        for date in [
+            "2024-02-29T12:30:00.12345678é",
+            "2024-02-29T99999999999999999:00Z",
+            "99999999999999999-01-01",
+            "Thursday, 29-Feb-99999999999999999 00:00:00 GMT",
+        ] {
+            let content = format!("noarchive, unavailable_after: {}", date);
+            let directives = RobotsDirectives::parse(&content);
+            assert_eq!(directives.unavailable_after, None);
+            assert!(directives.noarchive);
+
+            let input = format!(r#"<meta name="robots" content="{}">"#, content);
+            let html = crate::HTML::from_string(input, None).unwrap();
+            assert_eq!(html.robots.all.unavailable_after, None);
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let robots = Robots::from_header_values(vec![
+            "max-snippet: 20",
+            "googlebot: nofollow, max-snippet: 10",
+            "otherbot: noindex",
+        ]);
+        assert_eq!(robots.all.max_snippet, Some(20));
+        assert_eq!(robots.crawlers.len(), 2);
+
+        let googlebot = robots.for_crawler("Googlebot");
+        assert!(googlebot.nofollow);
+        assert!(googlebot.is_indexable());
+        assert_eq!(googlebot.max_snippet, Some(10));
+
+        assert!(!robots.for_crawler("otherbot").is_indexable());
+        assert!(robots.for_crawler("bingbot").is_indexable());
+    }
+}
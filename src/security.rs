@@ -0,0 +1,495 @@
+//! Audit of security related HTTP response headers
+
+use crate::headers;
+
+/// Recommended minimum HSTS max-age: 180 days
+const HSTS_MIN_MAX_AGE: u64 = 180 * 24 * 3600;
+/// HSTS max-age required for preloading: 1 year
+const HSTS_PRELOAD_MAX_AGE: u64 = 365 * 24 * 3600;
+
+/// Security related response headers, and the issues found with them
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct SecurityReport {
+    /// Strict-Transport-Security
+    pub strict_transport_security: Option<StrictTransportSecurity>,
+    /// Content-Security-Policy (or the report-only variant when no policy is enforced)
+    pub content_security_policy: Option<ContentSecurityPolicy>,
+    /// X-Frame-Options
+    pub x_frame_options: Option<String>,
+    /// X-Content-Type-Options
+    pub x_content_type_options: Option<String>,
+    /// Referrer-Policy
+    pub referrer_policy: Option<String>,
+    /// Permissions-Policy, as features with their allowlist
+    pub permissions_policy: Option<Vec<(String, Vec<String>)>>,
+    /// Cross-Origin-Opener-Policy
+    pub cross_origin_opener_policy: Option<String>,
+    /// Cross-Origin-Embedder-Policy
+    pub cross_origin_embedder_policy: Option<String>,
+    /// Missing or weak settings
+    pub findings: Vec<Finding>,
+}
+
+/// Parsed Strict-Transport-Security header
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct StrictTransportSecurity {
+    /// Seconds the host should only be accessed over HTTPS
+    pub max_age: u64,
+    /// Whether the policy applies to all subdomains
+    pub include_subdomains: bool,
+    /// Whether the host consents to HSTS preloading
+    pub preload: bool,
+}
+
+/// Parsed Content-Security-Policy header
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct ContentSecurityPolicy {
+    /// Directives (lower cased) with their source expressions, in order
+    pub directives: Vec<(String, Vec<String>)>,
+    /// Whether the policy is only reported, not enforced
+    pub report_only: bool,
+}
+
+/// Issue found in the security headers
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Finding {
+    /// Header the finding applies to
+    pub header: String,
+    /// How serious the issue is
+    pub severity: Severity,
+    /// Description of the issue
+    pub message: String,
+}
+
+/// Severity of a finding
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+}
+
+impl StrictTransportSecurity {
+    /// Parse a header value such as `max-age=31536000; includeSubDomains; preload`
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut max_age = None;
+        let mut include_subdomains = false;
+        let mut preload = false;
+
+        for directive in value.split(';') {
+            let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+            let name = name.trim().to_ascii_lowercase();
+            match &name[..] {
+                "max-age" => max_age = value.trim().trim_matches('"').parse().ok(),
+                "includesubdomains" => include_subdomains = true,
+                "preload" => preload = true,
+                _ => (),
+            }
+        }
+
+        max_age.map(|max_age| Self {
+            max_age,
+            include_subdomains,
+            preload,
+        })
+    }
+}
+
+impl ContentSecurityPolicy {
+    /// Parse a header value such as `default-src 'self'; img-src *`
+    ///
+    /// Only the first policy is used when multiple are given (comma separated).
+    pub fn parse(value: &str, report_only: bool) -> Self {
+        let policy = value.split(',').next().unwrap_or_default();
+        let mut directives: Vec<(String, Vec<String>)> = Vec::new();
+
+        for directive in policy.split(';') {
+            let mut tokens = directive.split_ascii_whitespace();
+            if let Some(name) = tokens.next() {
+                let name = name.to_ascii_lowercase();
+                // the first occurrence of a directive wins
+                if !directives.iter().any(|(n, _)| *n == name) {
+                    directives.push((name, tokens.map(str::to_string).collect()));
+                }
+            }
+        }
+
+        Self {
+            directives,
+            report_only,
+        }
+    }
+
+    /// Source expressions of a directive
+    pub fn directive(&self, name: &str) -> Option<&[String]> {
+        self.directives
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, sources)| &sources[..])
+    }
+
+    /// Sources for the given fetch directive, falling back to `default-src`
+    pub fn effective_sources(&self, name: &str) -> Option<&[String]> {
+        self.directive(name)
+            .or_else(|| self.directive("default-src"))
+    }
+}
+
+impl SecurityReport {
+    /// Audit raw response header lines, `https` indicates the response was served over TLS
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::{SecurityReport, Severity};
+    ///
+    /// let headers = vec!["Strict-Transport-Security: max-age=600".to_string()];
+    /// let report = SecurityReport::from_headers(&headers, true);
+    ///
+    /// assert_eq!(report.strict_transport_security.unwrap().max_age, 600);
+    /// assert!(report.findings.iter().any(|f| f.severity == Severity::Medium));
+    /// ```
+    pub fn from_headers(headers: &[String], https: bool) -> Self {
+        let joined = |name: &str| {
+            let values: Vec<&str> = headers::values(headers, name).collect();
+            if values.is_empty() {
+                None
+            } else {
+                Some(values.join(", "))
+            }
+        };
+
+        let mut report = Self {
+            strict_transport_security: headers::value(headers, "strict-transport-security")
+                .and_then(StrictTransportSecurity::parse),
+            content_security_policy: headers::value(headers, "content-security-policy")
+                .map(|value| ContentSecurityPolicy::parse(value, false))
+                .or_else(|| {
+                    headers::value(headers, "content-security-policy-report-only")
+                        .map(|value| ContentSecurityPolicy::parse(value, true))
+                }),
+            x_frame_options: headers::value(headers, "x-frame-options").map(str::to_string),
+            x_content_type_options: headers::value(headers, "x-content-type-options")
+                .map(str::to_string),
+            referrer_policy: joined("referrer-policy"),
+            permissions_policy: joined("permissions-policy")
+                .map(|value| parse_permissions_policy(&value)),
+            cross_origin_opener_policy: headers::value(headers, "cross-origin-opener-policy")
+                .map(str::to_string),
+            cross_origin_embedder_policy: headers::value(headers, "cross-origin-embedder-policy")
+                .map(str::to_string),
+            findings: Vec::new(),
+        };
+
+        let hsts_present = headers::value(headers, "strict-transport-security").is_some();
+        report.audit_hsts(https, hsts_present);
+        report.audit_csp();
+        report.audit_framing();
+        report.audit_misc();
+
+        report
+    }
+
+    /// Highest severity among the findings
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|finding| finding.severity).max()
+    }
+
+    fn flag(&mut self, header: &str, severity: Severity, message: &str) {
+        self.findings.push(Finding {
+            header: header.to_string(),
+            severity,
+            message: message.to_string(),
+        });
+    }
+
+    fn audit_hsts(&mut self, https: bool, present: bool) {
+        const HEADER: &str = "Strict-Transport-Security";
+
+        let hsts = match (self.strict_transport_security.clone(), present) {
+            (_, true) if !https => {
+                return self.flag(HEADER, Severity::Info, "ignored over plain HTTP");
+            }
+            (None, true) => return self.flag(HEADER, Severity::Medium, "invalid max-age"),
+            (None, false) if https => return self.flag(HEADER, Severity::Medium, "missing"),
+            (None, false) => return,
+            (Some(hsts), _) => hsts,
+        };
+
+        if hsts.max_age == 0 {
+            self.flag(HEADER, Severity::Medium, "max-age=0 disables HSTS");
+        } else if hsts.max_age < HSTS_MIN_MAX_AGE {
+            self.flag(HEADER, Severity::Low, "max-age is shorter than 180 days");
+        }
+        if !hsts.include_subdomains {
+            self.flag(HEADER, Severity::Info, "does not include subdomains");
+        }
+        if hsts.preload && (!hsts.include_subdomains || hsts.max_age < HSTS_PRELOAD_MAX_AGE) {
+            self.flag(
+                HEADER,
+                Severity::Low,
+                "preload requires includeSubDomains and a max-age of at least 1 year",
+            );
+        }
+    }
+
+    fn audit_csp(&mut self) {
+        const HEADER: &str = "Content-Security-Policy";
+
+        let csp = match self.content_security_policy.clone() {
+            Some(csp) => csp,
+            None => return self.flag(HEADER, Severity::Medium, "missing"),
+        };
+
+        if csp.report_only {
+            self.flag(HEADER, Severity::Low, "policy is report-only, not enforced");
+        }
+
+        match csp.effective_sources("script-src") {
+            None => self.flag(HEADER, Severity::Medium, "scripts are not restricted"),
+            Some(sources) => {
+                let has = |source: &str| sources.iter().any(|s| s.eq_ignore_ascii_case(source));
+                let nonce_or_hash = sources.iter().any(|s| {
+                    let s = s.to_ascii_lowercase();
+                    s.starts_with("'nonce-") || s.starts_with("'sha")
+                });
+                // 'unsafe-inline' is ignored by browsers when a nonce or hash is present
+                if has("'unsafe-inline'") && !nonce_or_hash {
+                    self.flag(HEADER, Severity::Medium, "scripts allow 'unsafe-inline'");
+                }
+                if has("'unsafe-eval'") {
+                    self.flag(HEADER, Severity::Medium, "scripts allow 'unsafe-eval'");
+                }
+                if has("*") || has("http:") || has("https:") || has("data:") {
+                    self.flag(
+                        HEADER,
+                        Severity::Medium,
+                        "scripts may be loaded from any origin",
+                    );
+                }
+            }
+        }
+
+        let object_src = csp.effective_sources("object-src");
+        if !matches!(object_src, Some([none]) if none.eq_ignore_ascii_case("'none'")) {
+            self.flag(HEADER, Severity::Low, "object-src is not 'none'");
+        }
+        if csp.directive("base-uri").is_none() {
+            self.flag(HEADER, Severity::Low, "base-uri is not restricted");
+        }
+    }
+
+    fn audit_framing(&mut self) {
+        let frame_ancestors = self
+            .content_security_policy
+            .as_ref()
+            .filter(|csp| !csp.report_only)
+            .and_then(|csp| csp.directive("frame-ancestors"))
+            .is_some();
+
+        match self.x_frame_options.clone() {
+            None if !frame_ancestors => self.flag(
+                "X-Frame-Options",
+                Severity::Medium,
+                "missing, and no CSP frame-ancestors: the page can be framed (clickjacking)",
+            ),
+            Some(value)
+                if !value.eq_ignore_ascii_case("deny")
+                    && !value.eq_ignore_ascii_case("sameorigin") =>
+            {
+                self.flag(
+                    "X-Frame-Options",
+                    Severity::Low,
+                    "unsupported value, use DENY or SAMEORIGIN",
+                )
+            }
+            _ => (),
+        }
+    }
+
+    fn audit_misc(&mut self) {
+        match self.x_content_type_options.clone() {
+            Some(value) if value.eq_ignore_ascii_case("nosniff") => (),
+            Some(_) => self.flag("X-Content-Type-Options", Severity::Low, "should be nosniff"),
+            None => self.flag("X-Content-Type-Options", Severity::Low, "missing"),
+        }
+
+        match self.referrer_policy.clone() {
+            None => self.flag(
+                "Referrer-Policy",
+                Severity::Info,
+                "missing, browsers default to strict-origin-when-cross-origin",
+            ),
+            Some(value) => {
+                // the last recognized policy applies
+                let policy = value.rsplit(',').next().unwrap_or_default().trim();
+                if policy.eq_ignore_ascii_case("unsafe-url") {
+                    self.flag(
+                        "Referrer-Policy",
+                        Severity::Medium,
+                        "unsafe-url leaks full URLs to other origins",
+                    );
+                } else if policy.eq_ignore_ascii_case("no-referrer-when-downgrade") {
+                    self.flag(
+                        "Referrer-Policy",
+                        Severity::Low,
+                        "leaks full URLs to other HTTPS origins",
+                    );
+                }
+            }
+        }
+
+        match self.permissions_policy.clone() {
+            None => self.flag("Permissions-Policy", Severity::Info, "missing"),
+            Some(features) => {
+                for (feature, allowlist) in features {
+                    let powerful = ["camera", "microphone", "geolocation", "usb", "payment"];
+                    if powerful.contains(&&feature[..]) && allowlist.iter().any(|a| a == "*") {
+                        let message = format!("{} is allowed for all origins", feature);
+                        self.flag("Permissions-Policy", Severity::Low, &message);
+                    }
+                }
+            }
+        }
+
+        match self.cross_origin_opener_policy.as_deref() {
+            None => self.flag("Cross-Origin-Opener-Policy", Severity::Info, "missing"),
+            Some(value) if value.eq_ignore_ascii_case("unsafe-none") => self.flag(
+                "Cross-Origin-Opener-Policy",
+                Severity::Info,
+                "unsafe-none does not isolate the browsing context",
+            ),
+            _ => (),
+        }
+        if self.cross_origin_embedder_policy.is_none() {
+            self.flag("Cross-Origin-Embedder-Policy", Severity::Info, "missing");
+        }
+    }
+}
+
+/// Parse a Permissions-Policy structured header, e.g. `camera=(), geolocation=(self "https://a")`
+fn parse_permissions_policy(value: &str) -> Vec<(String, Vec<String>)> {
+    value
+        .split(',')
+        .filter_map(|member| {
+            let (feature, allowlist) = member.split_once('=')?;
+            let allowlist = allowlist.split(';').next().unwrap_or_default().trim();
+            let allowlist = allowlist
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .split_ascii_whitespace()
+                .map(|origin| origin.trim_matches('"').to_string())
+                .collect();
+            Some((feature.trim().to_ascii_lowercase(), allowlist))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_hardened() {
+        let report = SecurityReport::from_headers(
+            &headers(&[
+                "HTTP/2 200",
+                "strict-transport-security: max-age=63072000; includeSubDomains; preload",
+                "content-security-policy: default-src 'self'; script-src 'self' 'nonce-abc' 'unsafe-inline'; object-src 'none'; base-uri 'none'; frame-ancestors 'none'",
+                "x-content-type-options: nosniff",
+                "referrer-policy: strict-origin-when-cross-origin",
+                "permissions-policy: camera=(), geolocation=(self \"https://maps.example\")",
+                "cross-origin-opener-policy: same-origin",
+                "cross-origin-embedder-policy: require-corp",
+            ]),
+            true,
+        );
+
+        assert_eq!(report.findings, vec![]);
+        assert_eq!(
+            report.strict_transport_security,
+            Some(StrictTransportSecurity {
+                max_age: 63072000,
+                include_subdomains: true,
+                preload: true
+            })
+        );
+        let csp = report.content_security_policy.unwrap();
+        assert_eq!(
+            csp.directive("object-src"),
+            Some(&["'none'".to_string()][..])
+        );
+        assert_eq!(csp.effective_sources("img-src").unwrap(), ["'self'"]);
+        assert_eq!(
+            report.permissions_policy.unwrap()[1],
+            (
+                "geolocation".to_string(),
+                vec!["self".to_string(), "https://maps.example".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        let report = SecurityReport::from_headers(&headers(&["HTTP/1.1 200 OK"]), true);
+        assert_eq!(report.max_severity(), Some(Severity::Medium));
+
+        let flagged = |header: &str| {
+            report
+                .findings
+                .iter()
+                .find(|finding| finding.header == header)
+                .map(|finding| finding.severity)
+        };
+        assert_eq!(flagged("Strict-Transport-Security"), Some(Severity::Medium));
+        assert_eq!(flagged("Content-Security-Policy"), Some(Severity::Medium));
+        assert_eq!(flagged("X-Frame-Options"), Some(Severity::Medium));
+        assert_eq!(flagged("X-Content-Type-Options"), Some(Severity::Low));
+        assert_eq!(flagged("Referrer-Policy"), Some(Severity::Info));
+
+        // HSTS is not expected over plain HTTP
+        let report = SecurityReport::from_headers(&headers(&["HTTP/1.1 200 OK"]), false);
+        assert!(report
+            .findings
+            .iter()
+            .all(|finding| finding.header != "Strict-Transport-Security"));
+    }
+
+    #[test]
+    fn test_weak() {
+        let report = SecurityReport::from_headers(
+            &headers(&[
+                "Strict-Transport-Security: max-age=3600; preload",
+                "Content-Security-Policy-Report-Only: script-src * 'unsafe-eval'",
+                "X-Frame-Options: ALLOW-FROM https://example.org",
+                "Referrer-Policy: unsafe-url",
+            ]),
+            true,
+        );
+        let messages: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|finding| finding.severity >= Severity::Low)
+            .map(|finding| &finding.message[..])
+            .collect();
+
+        assert!(messages.contains(&"max-age is shorter than 180 days"));
+        assert!(messages.contains(&"policy is report-only, not enforced"));
+        assert!(messages.contains(&"scripts allow 'unsafe-eval'"));
+        assert!(messages.contains(&"scripts may be loaded from any origin"));
+        assert!(messages.contains(&"unsupported value, use DENY or SAMEORIGIN"));
+        assert!(messages.contains(&"unsafe-url leaks full URLs to other origins"));
+    }
+}
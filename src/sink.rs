@@ -0,0 +1,432 @@
+//! Arena backed document, built directly by the html5ever tree builder
+//!
+//! Nodes live in a single `Vec` and refer to each other by index, instead of the reference
+//! counted nodes with a `Vec` of children each that `markup5ever_rcdom` allocates. Links between
+//! nodes take 32 bits, and the arena is sized up front from the number of tags in the input.
+//! Comments and doctypes are not retained. Traversal is iterative, so deeply nested documents can
+//! not overflow the stack.
+
+use std::borrow::Cow;
+use std::num::NonZeroU32;
+
+use html5ever::driver::ParseOpts;
+use html5ever::interface::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
+use html5ever::parse_document;
+use html5ever::tendril::{StrTendril, TendrilSink};
+use html5ever::{Attribute, ExpandedName, QualName};
+
+use crate::text::TextWriter;
+
+/// Index of a node in the document
+pub(crate) type NodeId = usize;
+
+/// Optional reference to a node, stored in 32 bits to keep nodes small
+#[derive(Copy, Clone)]
+pub(crate) struct Link(Option<NonZeroU32>);
+
+impl Link {
+    const NONE: Self = Self(None);
+
+    fn to(id: NodeId) -> Self {
+        // offset by one, so the root is not zero
+        let id = u32::try_from(id + 1).expect("document of fewer than 2^32 nodes");
+        Self(NonZeroU32::new(id))
+    }
+
+    fn get(self) -> Option<NodeId> {
+        self.0.map(|id| id.get() as usize - 1)
+    }
+}
+
+impl From<Option<NodeId>> for Link {
+    fn from(id: Option<NodeId>) -> Self {
+        id.map_or(Self::NONE, Self::to)
+    }
+}
+
+pub(crate) enum NodeData {
+    /// The document root, or the contents of a \<template\>
+    Document,
+    /// Doctype, comment or processing instruction
+    Other,
+    Text(StrTendril),
+    Element {
+        name: QualName,
+        attrs: Vec<Attribute>,
+        template_contents: Link,
+        mathml_annotation_xml_integration_point: bool,
+    },
+}
+
+pub(crate) struct Node {
+    parent: Link,
+    first_child: Link,
+    last_child: Link,
+    prev_sibling: Link,
+    next_sibling: Link,
+    pub data: NodeData,
+}
+
+/// Parsed HTML document
+pub(crate) struct Document {
+    nodes: Vec<Node>,
+}
+
+impl Document {
+    /// The document root
+    pub const ROOT: NodeId = 0;
+
+    fn with_capacity(capacity: usize) -> Self {
+        let mut document = Self {
+            nodes: Vec::with_capacity(capacity),
+        };
+        document.create(NodeData::Document);
+        document
+    }
+
+    /// Number of nodes, node ids are below this
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent.get()
+    }
+
+    /// Children of the node, in document order
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut next = self.nodes[id].first_child.get();
+        std::iter::from_fn(move || {
+            let current = next?;
+            next = self.nodes[current].next_sibling.get();
+            Some(current)
+        })
+    }
+
+    /// Local tag name, for elements
+    pub fn tag_name(&self, id: NodeId) -> Option<&str> {
+        match self.nodes[id].data {
+            NodeData::Element { ref name, .. } => Some(name.local.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Value of the attribute with the given local name, for elements
+    pub fn attribute(&self, id: NodeId, name: &str) -> Option<&str> {
+        match self.nodes[id].data {
+            NodeData::Element { ref attrs, .. } => attrs
+                .iter()
+                .find(|attr| attr.name.local.as_ref() == name)
+                .map(|attr| attr.value.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Contents of the first text child, trimmed
+    pub fn first_text(&self, id: NodeId) -> Option<&str> {
+        self.children(id)
+            .find_map(|child| match self.nodes[child].data {
+                NodeData::Text(ref contents) => Some(contents.trim()),
+                _ => None,
+            })
+    }
+
+    /// Text of all descendants, whitespace collapsed and script contents skipped
+    pub fn text(&self, id: NodeId) -> String {
+        let mut text = TextWriter::default();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            match self.nodes[id].data {
+                NodeData::Text(ref contents) => text.push(contents),
+                NodeData::Element { ref name, .. }
+                    if matches!(name.local.as_ref(), "script" | "style") => {}
+                _ => {
+                    let children_start = stack.len();
+                    stack.extend(self.children(id));
+                    stack[children_start..].reverse();
+                }
+            }
+        }
+        text.finish()
+    }
+
+    /// Parse an HTML document
+    pub fn parse(input: &str) -> Self {
+        // an element per start tag and about as many text nodes, but no more than one node per
+        // few bytes of input for inputs full of stray `<`
+        let start_tags = input
+            .as_bytes()
+            .windows(2)
+            .filter(|pair| pair[0] == b'<' && pair[1].is_ascii_alphabetic())
+            .count();
+        let capacity = (2 * start_tags).min(input.len() / 4) + 1;
+
+        let sink = Sink {
+            document: Document::with_capacity(capacity),
+        };
+        parse_document(sink, ParseOpts::default()).one(input)
+    }
+
+    /// Convert a DOM built by `markup5ever_rcdom`
+    #[cfg(test)]
+    pub fn from_rcdom(dom: &markup5ever_rcdom::RcDom) -> Self {
+        use markup5ever_rcdom::{Handle, NodeData as RcNodeData};
+
+        let mut document = Self::with_capacity(0);
+        let mut stack: Vec<(Handle, NodeId)> = vec![(dom.document.clone(), Self::ROOT)];
+        while let Some((handle, id)) = stack.pop() {
+            for child in handle.children.borrow().iter() {
+                let data = match child.data {
+                    RcNodeData::Document => NodeData::Document,
+                    RcNodeData::Text { ref contents } => NodeData::Text(contents.borrow().clone()),
+                    RcNodeData::Element {
+                        ref name,
+                        ref attrs,
+                        mathml_annotation_xml_integration_point,
+                        ..
+                    } => NodeData::Element {
+                        name: name.clone(),
+                        attrs: attrs.borrow().clone(),
+                        template_contents: Link::NONE,
+                        mathml_annotation_xml_integration_point,
+                    },
+                    _ => NodeData::Other,
+                };
+                let child_id = document.create(data);
+                document.append_node(id, child_id);
+                stack.push((child.clone(), child_id));
+            }
+        }
+        document
+    }
+
+    fn create(&mut self, data: NodeData) -> NodeId {
+        self.nodes.push(Node {
+            parent: Link::NONE,
+            first_child: Link::NONE,
+            last_child: Link::NONE,
+            prev_sibling: Link::NONE,
+            next_sibling: Link::NONE,
+            data,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn detach(&mut self, id: NodeId) {
+        let node = &mut self.nodes[id];
+        let (parent, prev, next) = (node.parent, node.prev_sibling, node.next_sibling);
+        node.parent = Link::NONE;
+        node.prev_sibling = Link::NONE;
+        node.next_sibling = Link::NONE;
+
+        let parent = match parent.get() {
+            Some(parent) => parent,
+            None => return,
+        };
+        match prev.get() {
+            Some(prev) => self.nodes[prev].next_sibling = next,
+            None => self.nodes[parent].first_child = next,
+        }
+        match next.get() {
+            Some(next) => self.nodes[next].prev_sibling = prev,
+            None => self.nodes[parent].last_child = prev,
+        }
+    }
+
+    fn append_node(&mut self, parent: NodeId, id: NodeId) {
+        self.detach(id);
+        let last = self.nodes[parent].last_child;
+        self.nodes[id].parent = Link::to(parent);
+        self.nodes[id].prev_sibling = last;
+        match last.get() {
+            Some(last) => self.nodes[last].next_sibling = Link::to(id),
+            None => self.nodes[parent].first_child = Link::to(id),
+        }
+        self.nodes[parent].last_child = Link::to(id);
+    }
+
+    fn insert_before(&mut self, sibling: NodeId, id: NodeId) {
+        self.detach(id);
+        let parent = self.nodes[sibling].parent;
+        let prev = self.nodes[sibling].prev_sibling;
+        self.nodes[id].parent = parent;
+        self.nodes[id].prev_sibling = prev;
+        self.nodes[id].next_sibling = Link::to(sibling);
+        self.nodes[sibling].prev_sibling = Link::to(id);
+        match (prev.get(), parent.get()) {
+            (Some(prev), _) => self.nodes[prev].next_sibling = Link::to(id),
+            (None, Some(parent)) => self.nodes[parent].first_child = Link::to(id),
+            (None, None) => (),
+        }
+    }
+
+    /// Append text to the node, if it is a text node
+    fn append_to_text(&mut self, id: Link, text: &StrTendril) -> bool {
+        match id.get().map(|id| &mut self.nodes[id].data) {
+            Some(NodeData::Text(ref mut contents)) => {
+                contents.push_tendril(text);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Tree sink building a [`Document`]
+pub(crate) struct Sink {
+    document: Document,
+}
+
+impl TreeSink for Sink {
+    type Handle = NodeId;
+    type Output = Document;
+
+    fn finish(self) -> Document {
+        self.document
+    }
+
+    fn parse_error(&mut self, _msg: Cow<'static, str>) {}
+
+    fn get_document(&mut self) -> NodeId {
+        Document::ROOT
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a NodeId) -> ExpandedName<'a> {
+        match self.document.nodes[*target].data {
+            NodeData::Element { ref name, .. } => name.expanded(),
+            _ => panic!("not an element"),
+        }
+    }
+
+    fn create_element(
+        &mut self,
+        name: QualName,
+        attrs: Vec<Attribute>,
+        flags: ElementFlags,
+    ) -> NodeId {
+        let template_contents = if flags.template {
+            Link::to(self.document.create(NodeData::Document))
+        } else {
+            Link::NONE
+        };
+        self.document.create(NodeData::Element {
+            name,
+            attrs,
+            template_contents,
+            mathml_annotation_xml_integration_point: flags.mathml_annotation_xml_integration_point,
+        })
+    }
+
+    fn create_comment(&mut self, _text: StrTendril) -> NodeId {
+        self.document.create(NodeData::Other)
+    }
+
+    fn create_pi(&mut self, _target: StrTendril, _data: StrTendril) -> NodeId {
+        self.document.create(NodeData::Other)
+    }
+
+    fn append(&mut self, parent: &NodeId, child: NodeOrText<NodeId>) {
+        let id = match child {
+            NodeOrText::AppendText(text) => {
+                let last = self.document.nodes[*parent].last_child;
+                if self.document.append_to_text(last, &text) {
+                    return;
+                }
+                self.document.create(NodeData::Text(text))
+            }
+            NodeOrText::AppendNode(id) => id,
+        };
+        self.document.append_node(*parent, id);
+    }
+
+    fn append_based_on_parent_node(
+        &mut self,
+        element: &NodeId,
+        prev_element: &NodeId,
+        child: NodeOrText<NodeId>,
+    ) {
+        if self.document.nodes[*element].parent.get().is_some() {
+            self.append_before_sibling(element, child);
+        } else {
+            self.append(prev_element, child);
+        }
+    }
+
+    fn append_doctype_to_document(
+        &mut self,
+        _name: StrTendril,
+        _public_id: StrTendril,
+        _system_id: StrTendril,
+    ) {
+        let id = self.document.create(NodeData::Other);
+        self.document.append_node(Document::ROOT, id);
+    }
+
+    fn get_template_contents(&mut self, target: &NodeId) -> NodeId {
+        let contents = match self.document.nodes[*target].data {
+            NodeData::Element {
+                template_contents, ..
+            } => template_contents.get(),
+            _ => None,
+        };
+        contents.expect("not a template element")
+    }
+
+    fn same_node(&self, x: &NodeId, y: &NodeId) -> bool {
+        x == y
+    }
+
+    fn set_quirks_mode(&mut self, _mode: QuirksMode) {}
+
+    fn append_before_sibling(&mut self, sibling: &NodeId, new_node: NodeOrText<NodeId>) {
+        let id = match new_node {
+            NodeOrText::AppendText(text) => {
+                let prev = self.document.nodes[*sibling].prev_sibling;
+                if self.document.append_to_text(prev, &text) {
+                    return;
+                }
+                self.document.create(NodeData::Text(text))
+            }
+            NodeOrText::AppendNode(id) => id,
+        };
+        self.document.insert_before(*sibling, id);
+    }
+
+    fn add_attrs_if_missing(&mut self, target: &NodeId, attrs: Vec<Attribute>) {
+        if let NodeData::Element {
+            attrs: ref mut existing,
+            ..
+        } = self.document.nodes[*target].data
+        {
+            for attr in attrs {
+                if !existing.iter().any(|e| e.name == attr.name) {
+                    existing.push(attr);
+                }
+            }
+        }
+    }
+
+    fn remove_from_parent(&mut self, target: &NodeId) {
+        self.document.detach(*target);
+    }
+
+    fn reparent_children(&mut self, node: &NodeId, new_parent: &NodeId) {
+        while let Some(child) = self.document.nodes[*node].first_child.get() {
+            self.document.append_node(*new_parent, child);
+        }
+    }
+
+    fn is_mathml_annotation_xml_integration_point(&self, target: &NodeId) -> bool {
+        matches!(
+            self.document.nodes[*target].data,
+            NodeData::Element {
+                mathml_annotation_xml_integration_point: true,
+                ..
+            }
+        )
+    }
+}
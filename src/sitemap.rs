@@ -0,0 +1,455 @@
+//! Sitemaps, see <https://www.sitemaps.org/protocol.html>
+
+use std::io::{self, Read};
+use std::time::SystemTime;
+
+use flate2::read::MultiGzDecoder;
+use markup5ever_rcdom::Handle;
+
+use crate::date::from_iso8601;
+use crate::xml;
+#[cfg(feature = "curl")]
+use crate::{Client, Request, WebpageOptions};
+
+/// Maximum uncompressed size of a sitemap, as set by the protocol
+const MAX_SITEMAP_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Maximum number of child sitemaps followed by [`Sitemap::fetch_all`]
+#[cfg(feature = "curl")]
+const MAX_CHILD_SITEMAPS: usize = 1000;
+
+/// Parsed sitemap: either a list of URLs, or a sitemap index listing other sitemaps
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Sitemap {
+    /// Pages listed in a \<urlset\> or text sitemap
+    pub entries: Vec<SitemapEntry>,
+    /// Child sitemaps listed in a \<sitemapindex\>
+    pub sitemaps: Vec<SitemapEntry>,
+}
+
+/// Page (or child sitemap) listed in a sitemap
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct SitemapEntry {
+    /// URL of the page
+    pub loc: String,
+    /// Time of last modification
+    pub lastmod: Option<SystemTime>,
+    /// How frequently the page is likely to change: always, hourly, daily, .., never
+    pub changefreq: Option<String>,
+    /// Priority relative to other pages on the site, between 0.0 and 1.0
+    pub priority: Option<f32>,
+    /// Image extension: images on the page
+    pub images: Vec<SitemapImage>,
+    /// Video extension: videos on the page
+    pub videos: Vec<SitemapVideo>,
+    /// News extension: article info
+    pub news: Option<SitemapNews>,
+}
+
+/// Image from the `image:` sitemap extension
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct SitemapImage {
+    pub loc: String,
+    pub title: Option<String>,
+    pub caption: Option<String>,
+}
+
+/// Video from the `video:` sitemap extension
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct SitemapVideo {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub thumbnail_loc: Option<String>,
+    pub content_loc: Option<String>,
+    pub player_loc: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<u64>,
+    pub publication_date: Option<SystemTime>,
+}
+
+/// Article info from the `news:` sitemap extension
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct SitemapNews {
+    pub publication_name: Option<String>,
+    pub publication_language: Option<String>,
+    pub publication_date: Option<SystemTime>,
+    pub title: Option<String>,
+}
+
+impl Sitemap {
+    /// Parse an XML or text sitemap, optionally gzip compressed
+    ///
+    /// ## Examples
+    /// ```
+    /// use webpage::Sitemap;
+    ///
+    /// let input = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+    ///   <url><loc>https://example.org/</loc><priority>0.8</priority></url>
+    /// </urlset>"#;
+    /// let sitemap = Sitemap::parse(input.as_bytes()).unwrap();
+    /// assert_eq!(sitemap.entries[0].loc, "https://example.org/");
+    /// assert_eq!(sitemap.entries[0].priority, Some(0.8));
+    /// ```
+    pub fn parse(input: &[u8]) -> Result<Self, io::Error> {
+        if input.starts_with(&[0x1f, 0x8b]) {
+            // read one byte past the limit to tell a sitemap of exactly the limit from a larger one
+            let mut decoded = Vec::new();
+            MultiGzDecoder::new(input)
+                .take(MAX_SITEMAP_SIZE + 1)
+                .read_to_end(&mut decoded)?;
+            if decoded.len() as u64 > MAX_SITEMAP_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "sitemap exceeds 50 MiB uncompressed",
+                ));
+            }
+            return Self::parse_uncompressed(&decoded);
+        }
+        Self::parse_uncompressed(input)
+    }
+
+    fn parse_uncompressed(input: &[u8]) -> Result<Self, io::Error> {
+        let start = input
+            .strip_prefix("\u{feff}".as_bytes())
+            .unwrap_or(input)
+            .iter()
+            .find(|b| !b.is_ascii_whitespace());
+        if start == Some(&b'<') {
+            Self::from_xml(input)
+        } else {
+            Ok(Self::from_text(&String::from_utf8_lossy(input)))
+        }
+    }
+
+    /// Text sitemap: one URL per line
+    fn from_text(input: &str) -> Self {
+        let entries = input
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("http://") || line.starts_with("https://"))
+            .map(|line| SitemapEntry {
+                loc: line.to_string(),
+                ..SitemapEntry::default()
+            })
+            .collect();
+
+        Self {
+            entries,
+            sitemaps: Vec::new(),
+        }
+    }
+
+    fn from_xml(input: &[u8]) -> Result<Self, io::Error> {
+        let document = xml::parse(input)?;
+        let root = &document.root;
+
+        let mut sitemap = Self::default();
+        for child in xml::children(root) {
+            match (xml::name(root), xml::name(&child)) {
+                ("urlset", "url") => sitemap.entries.extend(entry(&child)),
+                ("sitemapindex", "sitemap") => sitemap.sitemaps.extend(entry(&child)),
+                _ => (),
+            }
+        }
+        Ok(sitemap)
+    }
+
+    /// Sitemap URLs from the `Sitemap:` lines in a robots.txt file
+    pub fn from_robots_txt(robots_txt: &str) -> Vec<String> {
+        robots_txt
+            .lines()
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                if name.trim().eq_ignore_ascii_case("sitemap") {
+                    // strip trailing comments
+                    let value = value.split('#').next().unwrap_or_default().trim();
+                    Some(value.to_string()).filter(|value| !value.is_empty())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Sitemap {
+    /// Fetch and parse a sitemap
+    pub fn fetch(url: &str, options: WebpageOptions) -> Result<Self, io::Error> {
+        Client::new(options).fetch_sitemap(url)
+    }
+
+    /// Find the sitemaps of a site, from its robots.txt or else at `/sitemap.xml`
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use webpage::{Sitemap, WebpageOptions};
+    ///
+    /// let sitemaps = Sitemap::discover("https://example.org", WebpageOptions::default());
+    /// for url in sitemaps.unwrap() {
+    ///     let sitemap = Sitemap::fetch(&url, WebpageOptions::default());
+    /// }
+    /// ```
+    pub fn discover(site_url: &str, options: WebpageOptions) -> Result<Vec<String>, io::Error> {
+        Client::new(options).discover_sitemaps(site_url)
+    }
+
+    /// Fetch a sitemap, following a sitemap index to collect the entries of all child sitemaps
+    ///
+    /// Child sitemaps that fail to fetch or parse are skipped, only an error for the sitemap at
+    /// `url` itself is returned.
+    pub fn fetch_all(url: &str, options: WebpageOptions) -> Result<Vec<SitemapEntry>, io::Error> {
+        let client = Client::new(options);
+        let sitemap = client.fetch_sitemap(url)?;
+
+        let mut entries = sitemap.entries;
+        for child in sitemap.sitemaps.iter().take(MAX_CHILD_SITEMAPS) {
+            // an index may not list other indexes, so these are not followed further
+            if let Ok(child) = client.fetch_sitemap(&child.loc) {
+                entries.extend(child.entries);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(feature = "curl")]
+impl Client {
+    /// Fetch and parse a sitemap
+    pub fn fetch_sitemap(&self, url: &str) -> Result<Sitemap, io::Error> {
+        let (http, body) = self.send_raw(Request::get(url))?;
+        if !(200..300).contains(&http.response_code) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("HTTP status {} for sitemap {}", http.response_code, url),
+            ));
+        }
+        Sitemap::parse(&body)
+    }
+
+    /// Find the sitemaps of a site, from its robots.txt or else at `/sitemap.xml`
+    pub fn discover_sitemaps(&self, site_url: &str) -> Result<Vec<String>, io::Error> {
+        let base = url::Url::parse(site_url)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let robots_txt = base.join("/robots.txt").expect("valid path");
+
+        let http = self.send(Request::get(robots_txt.as_str()))?;
+        if http.response_code == 200 {
+            let sitemaps = Sitemap::from_robots_txt(&http.body);
+            if !sitemaps.is_empty() {
+                return Ok(sitemaps);
+            }
+        }
+
+        let fallback = base.join("/sitemap.xml").expect("valid path");
+        let http = self.send(Request::head(fallback.as_str()))?;
+        if http.response_code == 200 {
+            Ok(vec![fallback.to_string()])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Parse a \<url\> or \<sitemap\> element, skipping it when there is no location
+fn entry(handle: &Handle) -> Option<SitemapEntry> {
+    let mut entry = SitemapEntry {
+        loc: xml::child_text(handle, "loc")?,
+        ..SitemapEntry::default()
+    };
+
+    for child in xml::children(handle) {
+        match xml::name(&child) {
+            "lastmod" => entry.lastmod = from_iso8601(&xml::text(&child)),
+            "changefreq" => entry.changefreq = Some(xml::text(&child).to_ascii_lowercase()),
+            "priority" => entry.priority = xml::text(&child).parse().ok(),
+            "image" => entry.images.extend(image(&child)),
+            "video" => entry.videos.push(video(&child)),
+            "news" => entry.news = Some(news(&child)),
+            _ => (),
+        }
+    }
+
+    Some(entry)
+}
+
+fn image(handle: &Handle) -> Option<SitemapImage> {
+    Some(SitemapImage {
+        loc: xml::child_text(handle, "loc")?,
+        title: xml::child_text(handle, "title"),
+        caption: xml::child_text(handle, "caption"),
+    })
+}
+
+fn video(handle: &Handle) -> SitemapVideo {
+    SitemapVideo {
+        title: xml::child_text(handle, "title"),
+        description: xml::child_text(handle, "description"),
+        thumbnail_loc: xml::child_text(handle, "thumbnail_loc"),
+        content_loc: xml::child_text(handle, "content_loc"),
+        player_loc: xml::child_text(handle, "player_loc"),
+        duration: xml::child_text(handle, "duration").and_then(|d| d.parse().ok()),
+        publication_date: xml::child_text(handle, "publication_date")
+            .and_then(|d| from_iso8601(&d)),
+    }
+}
+
+fn news(handle: &Handle) -> SitemapNews {
+    let publication = xml::child(handle, "publication");
+    SitemapNews {
+        publication_name: publication
+            .as_ref()
+            .and_then(|p| xml::child_text(p, "name")),
+        publication_language: publication
+            .as_ref()
+            .and_then(|p| xml::child_text(p, "language")),
+        publication_date: xml::child_text(handle, "publication_date")
+            .and_then(|d| from_iso8601(&d)),
+        title: xml::child_text(handle, "title"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const URLSET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+        xmlns:video="http://www.google.com/schemas/sitemap-video/1.1"
+        xmlns:news="http://www.google.com/schemas/sitemap-news/0.9">
+  <url>
+    <loc>https://example.org/</loc>
+    <lastmod>2024-02-29</lastmod>
+    <changefreq>Daily</changefreq>
+    <priority>1.0</priority>
+    <image:image>
+      <image:loc>https://example.org/logo.png</image:loc>
+      <image:caption><![CDATA[The logo]]></image:caption>
+    </image:image>
+  </url>
+  <url>
+    <loc>https://example.org/news?a=1&amp;b=2</loc>
+    <news:news>
+      <news:publication>
+        <news:name>Example Times</news:name>
+        <news:language>en</news:language>
+      </news:publication>
+      <news:publication_date>2024-02-29T12:00:00+00:00</news:publication_date>
+      <news:title>Leap day</news:title>
+    </news:news>
+    <video:video>
+      <video:thumbnail_loc>https://example.org/thumb.jpg</video:thumbnail_loc>
+      <video:title>Clip</video:title>
+      <video:duration>600</video:duration>
+    </video:video>
+  </url>
+  <url><lastmod>2024-01-01</lastmod></url>
+</urlset>"#;
+
+    #[test]
+    fn test_urlset() {
+        let sitemap = Sitemap::parse(URLSET.as_bytes()).unwrap();
+        assert_eq!(sitemap.entries.len(), 2);
+        assert!(sitemap.sitemaps.is_empty());
+
+        let home = &sitemap.entries[0];
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_164_800);
+        assert_eq!(home.lastmod, Some(leap_day));
+        assert_eq!(home.changefreq, Some("daily".to_string()));
+        assert_eq!(home.priority, Some(1.0));
+        assert_eq!(home.images[0].loc, "https://example.org/logo.png");
+        assert_eq!(home.images[0].caption, Some("The logo".to_string()));
+
+        let article = &sitemap.entries[1];
+        assert_eq!(article.loc, "https://example.org/news?a=1&b=2");
+        let news = article.news.as_ref().unwrap();
+        assert_eq!(news.publication_name, Some("Example Times".to_string()));
+        assert_eq!(
+            news.publication_date,
+            Some(leap_day + Duration::from_secs(12 * 3600))
+        );
+        assert_eq!(article.videos[0].duration, Some(600));
+        assert_eq!(article.videos[0].title, Some("Clip".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        let input = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.org/a</loc><lastmod>99999999999999999-01-01</lastmod></url>
+  <url><loc>https://example.org/b</loc><lastmod>2024-02-29T99999999999999999:00Z</lastmod></url>
+  <url><loc>https://example.org/c</loc><lastmod>2024-02-29T12:30:00.12345678é</lastmod></url>
+</urlset>"#;
+        let sitemap = Sitemap::parse(input.as_bytes()).unwrap();
+        assert_eq!(sitemap.entries.len(), 3);
+        assert!(sitemap.entries.iter().all(|entry| entry.lastmod.is_none()));
+    }
+
+    #[test]
+    fn test_index_gzip() {
+        let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+            <sitemap><loc>https://example.org/a.xml.gz</loc><lastmod>2024-02-29</lastmod></sitemap>
+            <sitemap><loc>https://example.org/b.xml.gz</loc></sitemap>
+        </sitemapindex>"#;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(index.as_bytes()).unwrap();
+
+        let sitemap = Sitemap::parse(&encoder.finish().unwrap()).unwrap();
+        assert!(sitemap.entries.is_empty());
+        assert_eq!(sitemap.sitemaps.len(), 2);
+        assert_eq!(sitemap.sitemaps[1].loc, "https://example.org/b.xml.gz");
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        let depth = 10_000;
+        let input = format!(
+            "<urlset><url><loc>{}https://example.org/{}</loc></url></urlset>",
+            "<b>".repeat(depth),
+            "</b>".repeat(depth)
+        );
+        let sitemap = Sitemap::parse(input.as_bytes()).unwrap();
+        assert_eq!(sitemap.entries[0].loc, "https://example.org/");
+    }
+
+    #[test]
+    fn test_gzip_size_limit() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder
+            .write_all(&vec![b' '; MAX_SITEMAP_SIZE as usize + 1])
+            .unwrap();
+        let error = Sitemap::parse(&encoder.finish().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_text_and_robots_txt() {
+        let sitemap =
+            Sitemap::parse(b"https://example.org/\n\nhttps://example.org/about\r\n").unwrap();
+        assert_eq!(sitemap.entries.len(), 2);
+        assert_eq!(sitemap.entries[1].loc, "https://example.org/about");
+
+        let robots_txt = "User-agent: *\nDisallow: /private\nSitemap: https://example.org/sitemap.xml\nsitemap:https://example.org/news.xml # news\n";
+        assert_eq!(
+            Sitemap::from_robots_txt(robots_txt),
+            vec![
+                "https://example.org/sitemap.xml",
+                "https://example.org/news.xml"
+            ]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Plain text rendering, with line breaks between blocks
+
+/// Elements separated by a blank line
+const PARAGRAPH_TAGS: [&str; 13] = [
+    "address",
+    "blockquote",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "p",
+    "pre",
+    "table",
+];
+
+/// Elements starting on a new line, other elements are inline
+const LINE_TAGS: [&str; 28] = [
+    "article",
+    "aside",
+    "body",
+    "caption",
+    "center",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "footer",
+    "form",
+    "header",
+    "hgroup",
+    "legend",
+    "li",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "option",
+    "section",
+    "summary",
+    "tr",
+    "ul",
+];
+
+/// Builds plain text from text nodes and element boundaries
+///
+/// Whitespace is collapsed like CSS `white-space: normal` does, so non-breaking spaces are kept.
+/// Text inside \<pre\> is kept as is.
+#[derive(Default)]
+pub(crate) struct TextWriter {
+    text: String,
+    /// Whitespace was collapsed, and is written before the next text
+    space: bool,
+    /// Depth of \<pre\> elements
+    pre: usize,
+}
+
+impl TextWriter {
+    pub fn push(&mut self, contents: &str) {
+        if self.pre > 0 {
+            self.text.push_str(contents);
+            return;
+        }
+        for c in contents.chars() {
+            if is_collapsible(c) {
+                self.space = true;
+                continue;
+            }
+            if self.space && !self.text.is_empty() && !self.text.ends_with('\n') {
+                self.text.push(' ');
+            }
+            self.space = false;
+            self.text.push(c);
+        }
+    }
+
+    pub fn start(&mut self, tag_name: &str) {
+        self.separate(tag_name);
+        if tag_name == "pre" {
+            self.pre += 1;
+        }
+    }
+
+    pub fn end(&mut self, tag_name: &str) {
+        if tag_name == "pre" {
+            self.pre = self.pre.saturating_sub(1);
+        }
+        if tag_name != "br" {
+            self.separate(tag_name);
+        }
+    }
+
+    /// Whether whitespace is kept as is
+    pub fn is_preformatted(&self) -> bool {
+        self.pre > 0
+    }
+
+    pub fn finish(self) -> String {
+        self.text.trim_matches(is_collapsible).to_string()
+    }
+
+    fn separate(&mut self, tag_name: &str) {
+        match tag_name {
+            "br" => {
+                self.trim_end();
+                self.text.push('\n');
+            }
+            "td" | "th" => self.space = true,
+            tag_name if PARAGRAPH_TAGS.contains(&tag_name) => self.newlines(2),
+            tag_name if LINE_TAGS.contains(&tag_name) => self.newlines(1),
+            _ => (),
+        }
+    }
+
+    /// End the current line, with at least the given number of line breaks
+    fn newlines(&mut self, count: usize) {
+        self.trim_end();
+        if self.text.is_empty() {
+            return;
+        }
+        let present = self.text.len() - self.text.trim_end_matches('\n').len();
+        for _ in present..count {
+            self.text.push('\n');
+        }
+    }
+
+    fn trim_end(&mut self) {
+        let trimmed = self.text.trim_end_matches([' ', '\t']).len();
+        self.text.truncate(trimmed);
+        self.space = false;
+    }
+}
+
+/// Whitespace collapsed by CSS, which excludes the non-breaking space
+fn is_collapsible(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(events: &[(&str, &str)]) -> String {
+        let mut text = TextWriter::default();
+        for (event, value) in events {
+            match *event {
+                "start" => text.start(value),
+                "end" => text.end(value),
+                _ => text.push(value),
+            }
+        }
+        text.finish()
+    }
+
+    #[test]
+    fn test_blocks() {
+        let text = render(&[
+            ("start", "h1"),
+            ("text", " Title\n "),
+            ("end", "h1"),
+            ("start", "p"),
+            ("text", "A  \t b\u{a0}\u{a0}c "),
+            ("start", "br"),
+            ("end", "br"),
+            ("start", "br"),
+            ("end", "br"),
+            ("text", " d"),
+            ("end", "p"),
+            ("start", "ul"),
+            ("start", "li"),
+            ("text", "one"),
+            ("end", "li"),
+            ("start", "li"),
+            ("start", "span"),
+            ("text", "two"),
+            ("end", "span"),
+            ("end", "li"),
+            ("end", "ul"),
+            ("start", "pre"),
+            ("text", "  x\n    y"),
+            ("end", "pre"),
+            ("start", "td"),
+            ("text", "cell"),
+            ("end", "td"),
+            ("start", "td"),
+            ("text", "cell"),
+            ("end", "td"),
+        ]);
+        assert_eq!(
+            text,
+            "Title\n\nA b\u{a0}\u{a0}c\n\nd\n\none\ntwo\n\n  x\n    y\n\ncell cell"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Twitter Card information
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+/// Representing [Twitter Card](https://developer.x.com/en/docs/x-for-websites/cards/overview/markup)
+/// information, from the `twitter:*` meta tags
+pub struct TwitterCard {
+    /// Card type (summary, summary_large_image, app, player)
+    pub card: Option<String>,
+    /// @username of the website
+    pub site: Option<String>,
+    /// User ID of the website
+    pub site_id: Option<String>,
+    /// @username of the content creator
+    pub creator: Option<String>,
+    /// User ID of the content creator
+    pub creator_id: Option<String>,
+
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Image URL
+    pub image: Option<String>,
+    /// Alternative text of the image
+    pub image_alt: Option<String>,
+
+    /// URL of the iframe player
+    pub player: Option<String>,
+    /// Width of the player in pixels
+    pub player_width: Option<u32>,
+    /// Height of the player in pixels
+    pub player_height: Option<u32>,
+    /// URL of the raw video or audio stream
+    pub player_stream: Option<String>,
+
+    /// App on the iPhone App Store
+    pub app_iphone: Option<TwitterApp>,
+    /// App on the iPad App Store
+    pub app_ipad: Option<TwitterApp>,
+    /// App on Google Play
+    pub app_googleplay: Option<TwitterApp>,
+    /// Country of the App Store, when the app is not available in the US
+    pub app_country: Option<String>,
+
+    /// Other Twitter Card properties, e.g. `label1` and `data1`
+    pub properties: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+/// App advertised by a Twitter Card, for a single store
+pub struct TwitterApp {
+    pub name: Option<String>,
+    /// App ID in the store (numeric for the App Store, the package name for Google Play)
+    pub id: Option<String>,
+    /// URL opening the app, when installed
+    pub url: Option<String>,
+}
+
+impl TwitterCard {
+    /// Add a property, without its `twitter:` prefix
+    pub fn extend(&mut self, property: &str, content: String) {
+        match property {
+            "card" => self.card = Some(content),
+            "site" => self.site = Some(content),
+            "site:id" => self.site_id = Some(content),
+            "creator" => self.creator = Some(content),
+            "creator:id" => self.creator_id = Some(content),
+            "title" => self.title = Some(content),
+            "description" => self.description = Some(content),
+            "image" | "image:src" => self.image = Some(content),
+            "image:alt" => self.image_alt = Some(content),
+            "player" => self.player = Some(content),
+            "player:width" => self.player_width = content.parse().ok(),
+            "player:height" => self.player_height = content.parse().ok(),
+            "player:stream" => self.player_stream = Some(content),
+            "app:country" => self.app_country = Some(content),
+            _ => {
+                if !self.extend_app(property, &content) {
+                    self.properties.insert(property.to_string(), content);
+                }
+            }
+        }
+    }
+
+    /// Add an `app:{name,id,url}:{iphone,ipad,googleplay}` property
+    fn extend_app(&mut self, property: &str, content: &str) -> bool {
+        let mut parts = property.split(':');
+        let (field, store) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some("app"), Some(field @ ("name" | "id" | "url")), Some(store), None) => {
+                (field, store)
+            }
+            _ => return false,
+        };
+        let app = match store {
+            "iphone" => &mut self.app_iphone,
+            "ipad" => &mut self.app_ipad,
+            "googleplay" => &mut self.app_googleplay,
+            _ => return false,
+        };
+        let app = app.get_or_insert_with(TwitterApp::default);
+        let value = Some(content.to_string());
+        match field {
+            "name" => app.name = value,
+            "id" => app.id = value,
+            _ => app.url = value,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extend() {
+        let mut card = TwitterCard::default();
+        card.extend("card", "player".to_string());
+        card.extend("site", "@example".to_string());
+        card.extend("creator:id", "1234".to_string());
+        card.extend("image:src", "https://example.org/image.png".to_string());
+        card.extend("player:width", "480".to_string());
+        card.extend("player:height", "auto".to_string());
+        card.extend("label1", "Reading time".to_string());
+
+        assert_eq!(card.card, Some("player".to_string()));
+        assert_eq!(card.site, Some("@example".to_string()));
+        assert_eq!(card.creator_id, Some("1234".to_string()));
+        assert_eq!(
+            card.image,
+            Some("https://example.org/image.png".to_string())
+        );
+        assert_eq!(card.player_width, Some(480));
+        assert_eq!(card.player_height, None);
+        assert_eq!(
+            card.properties.get("label1"),
+            Some(&"Reading time".to_string())
+        );
+    }
+
+    #[test]
+    fn test_app() {
+        let mut card = TwitterCard::default();
+        card.extend("app:name:iphone", "Example".to_string());
+        card.extend("app:id:iphone", "307234931".to_string());
+        card.extend("app:url:googleplay", "example://open".to_string());
+        card.extend("app:id:windows", "ignored".to_string());
+
+        let iphone = card.app_iphone.unwrap();
+        assert_eq!(iphone.name, Some("Example".to_string()));
+        assert_eq!(iphone.id, Some("307234931".to_string()));
+        assert_eq!(iphone.url, None);
+        assert!(card.app_ipad.is_none());
+        assert_eq!(
+            card.app_googleplay.unwrap().url,
+            Some("example://open".to_string())
+        );
+        assert_eq!(card.properties.len(), 1);
+    }
+}
@@ -0,0 +1,525 @@
+//! Reading and writing [WARC](https://iipc.github.io/warc-specifications/) (ISO 28500) archives
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use flate2::bufread::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::date::{from_iso8601, to_iso8601};
+use crate::http::decode_body;
+use crate::{headers, Webpage, HTML, HTTP};
+
+const WARC_VERSION: &str = "WARC/1.1";
+
+/// A single WARC record: named header fields and a content block
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct WarcRecord {
+    /// Header fields, in order of appearance
+    pub headers: Vec<(String, String)>,
+    /// Content block
+    pub block: Vec<u8>,
+}
+
+impl WarcRecord {
+    /// Create a record with a fresh `WARC-Record-ID` and the given type, date and content
+    pub fn new(record_type: &str, date: SystemTime, content_type: &str, block: Vec<u8>) -> Self {
+        Self {
+            headers: vec![
+                ("WARC-Type".to_string(), record_type.to_string()),
+                ("WARC-Record-ID".to_string(), record_id()),
+                ("WARC-Date".to_string(), to_iso8601(date)),
+                ("Content-Type".to_string(), content_type.to_string()),
+            ],
+            block,
+        }
+    }
+
+    /// Value of the first header field with the given name (case insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| &value[..])
+    }
+
+    /// Add a header field
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// Record type (warcinfo, request, response, metadata, ..)
+    pub fn record_type(&self) -> Option<&str> {
+        self.header("WARC-Type")
+    }
+
+    /// Unique identifier of this record
+    pub fn record_id(&self) -> Option<&str> {
+        self.header("WARC-Record-ID")
+    }
+
+    /// URI of the original resource
+    pub fn target_uri(&self) -> Option<&str> {
+        self.header("WARC-Target-URI")
+    }
+
+    /// Time at which the content was captured
+    pub fn date(&self) -> Option<SystemTime> {
+        self.header("WARC-Date").and_then(from_iso8601)
+    }
+
+    /// Rebuild the HTTP transfer info from a `response` record
+    pub fn to_http(&self) -> Option<HTTP> {
+        if self.record_type() != Some("response") {
+            return None;
+        }
+
+        let (head, body) = match find(&self.block, b"\r\n\r\n") {
+            Some(pos) => (&self.block[..pos], &self.block[pos + 4..]),
+            None => (&self.block[..], &[][..]),
+        };
+        let headers: Vec<String> = String::from_utf8_lossy(head)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let response_code = headers
+            .first()
+            .and_then(|line| line.split(' ').nth(1))
+            .and_then(|code| code.parse().ok())
+            .unwrap_or_default();
+
+        let (text, raw_body) = decode_body(body);
+        Some(HTTP {
+            ip: self
+                .header("WARC-IP-Address")
+                .unwrap_or_default()
+                .to_string(),
+            transfer_time: Duration::ZERO,
+            redirect_count: 0,
+            content_type: headers::value(&headers, "content-type")
+                .unwrap_or_default()
+                .to_string(),
+            response_code,
+            url: self.target_uri().unwrap_or_default().to_string(),
+            body: text,
+            raw_body,
+            request_time: self.date().unwrap_or(SystemTime::UNIX_EPOCH),
+            exchanges: Vec::new(),
+            headers,
+        })
+    }
+
+    /// Rebuild the webpage (HTTP and HTML info) from a `response` record
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use webpage::WarcReader;
+    ///
+    /// let file = BufReader::new(File::open("crawl.warc.gz").unwrap());
+    /// for record in WarcReader::new(file) {
+    ///     if let Some(webpage) = record.unwrap().to_webpage() {
+    ///         println!("{:?}", webpage.unwrap().html.title);
+    ///     }
+    /// }
+    /// ```
+    pub fn to_webpage(&self) -> Option<Result<Webpage, io::Error>> {
+        let http = self.to_http()?;
+        let html = HTML::from_string(http.body.clone(), Some(http.url.clone()));
+        Some(html.map(|html| Webpage {
+            http,
+            html,
+            manifest: None,
+        }))
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "{}\r\n", WARC_VERSION)?;
+        for (name, value) in &self.headers {
+            write!(writer, "{}: {}\r\n", name, value)?;
+        }
+        write!(writer, "Content-Length: {}\r\n\r\n", self.block.len())?;
+        writer.write_all(&self.block)?;
+        writer.write_all(b"\r\n\r\n")
+    }
+}
+
+/// Writes WARC records, optionally compressing each record as a separate gzip member
+///
+/// ## Examples
+/// ```no_run
+/// use std::fs::File;
+/// use webpage::{WarcWriter, Webpage, WebpageOptions};
+///
+/// let mut options = WebpageOptions::default();
+/// options.record_transfer = true;
+/// let webpage = Webpage::from_url("http://example.org", options).unwrap();
+///
+/// let mut warc = WarcWriter::new(File::create("crawl.warc.gz").unwrap(), true);
+/// warc.write_warcinfo(&[("operator", "Example crawler")]).unwrap();
+/// warc.write_webpage(&webpage).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct WarcWriter<W: Write> {
+    writer: W,
+    gzip: bool,
+}
+
+impl<W: Write> WarcWriter<W> {
+    /// Create a writer, with gzip-per-record compression when `gzip` is set
+    pub fn new(writer: W, gzip: bool) -> Self {
+        Self { writer, gzip }
+    }
+
+    /// Unwrap the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write a single record
+    pub fn write_record(&mut self, record: &WarcRecord) -> io::Result<()> {
+        if self.gzip {
+            let mut encoder = GzEncoder::new(&mut self.writer, Compression::default());
+            record.write_to(&mut encoder)?;
+            encoder.finish()?;
+        } else {
+            record.write_to(&mut self.writer)?;
+        }
+        Ok(())
+    }
+
+    /// Write a `warcinfo` record describing the archive, with additional fields
+    pub fn write_warcinfo(&mut self, fields: &[(&str, &str)]) -> io::Result<()> {
+        let mut block = format!(
+            "software: {}/{}\r\nformat: WARC File Format 1.1\r\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+        for (name, value) in fields {
+            block.push_str(&format!("{}: {}\r\n", name, value));
+        }
+
+        let record = WarcRecord::new(
+            "warcinfo",
+            SystemTime::now(),
+            "application/warc-fields",
+            block.into_bytes(),
+        );
+        self.write_record(&record)
+    }
+
+    /// Write the `request` and `response` records of a HTTP transfer
+    ///
+    /// All redirects are included when the transfer was recorded
+    /// (`WebpageOptions.record_transfer`), otherwise only the final response is written.
+    /// Returns the record ID of the final response.
+    pub fn write_http(&mut self, http: &HTTP) -> io::Result<String> {
+        let mut response_id = String::new();
+
+        for exchange in &http.exchanges {
+            let mut response = WarcRecord::new(
+                "response",
+                exchange.started,
+                "application/http;msgtype=response",
+                http_message(&exchange.response_headers, &exchange.response_body),
+            );
+            response.set_header("WARC-Target-URI", &exchange.url);
+            if exchange.url == http.url && !http.ip.is_empty() {
+                response.set_header("WARC-IP-Address", &http.ip);
+            }
+
+            let mut request = WarcRecord::new(
+                "request",
+                exchange.started,
+                "application/http;msgtype=request",
+                http_message(&exchange.request_headers, &exchange.request_body),
+            );
+            request.set_header("WARC-Target-URI", &exchange.url);
+            request.set_header("WARC-Concurrent-To", response.record_id().unwrap());
+
+            self.write_record(&request)?;
+            self.write_record(&response)?;
+            response_id = response.record_id().unwrap().to_string();
+        }
+
+        if http.exchanges.is_empty() {
+            let mut response = WarcRecord::new(
+                "response",
+                http.request_time,
+                "application/http;msgtype=response",
+                http_message(&http.headers, http.body_bytes()),
+            );
+            response.set_header("WARC-Target-URI", &http.url);
+            if !http.ip.is_empty() {
+                response.set_header("WARC-IP-Address", &http.ip);
+            }
+            self.write_record(&response)?;
+            response_id = response.record_id().unwrap().to_string();
+        }
+
+        Ok(response_id)
+    }
+
+    /// Write the HTTP transfer of a webpage, followed by a `metadata` record with the fetch time
+    /// and the outlinks of the document
+    pub fn write_webpage(&mut self, webpage: &Webpage) -> io::Result<()> {
+        let response_id = self.write_http(&webpage.http)?;
+
+        let mut block = format!(
+            "fetchTimeMs: {}\r\n",
+            webpage.http.transfer_time.as_millis()
+        );
+        for link in &webpage.html.links {
+            block.push_str(&format!("outlink: {}\r\n", link.url));
+        }
+
+        let mut metadata = WarcRecord::new(
+            "metadata",
+            webpage.http.request_time,
+            "application/warc-fields",
+            block.into_bytes(),
+        );
+        metadata.set_header("WARC-Target-URI", &webpage.http.url);
+        metadata.set_header("WARC-Concurrent-To", &response_id);
+        self.write_record(&metadata)
+    }
+}
+
+/// Iterator over the records of a WARC archive, gzip compression is detected automatically
+#[derive(Debug)]
+pub struct WarcReader<R: BufRead> {
+    reader: Reader<R>,
+}
+
+#[derive(Debug)]
+enum Reader<R: BufRead> {
+    Plain(R),
+    Gzip(BufReader<MultiGzDecoder<R>>),
+}
+
+impl<R: BufRead> WarcReader<R> {
+    /// Create a reader for a plain or gzipped archive
+    pub fn new(mut reader: R) -> Self {
+        let gzip = reader
+            .fill_buf()
+            .map(|buf| buf.starts_with(&[0x1f, 0x8b]))
+            .unwrap_or(false);
+        let reader = if gzip {
+            Reader::Gzip(BufReader::new(MultiGzDecoder::new(reader)))
+        } else {
+            Reader::Plain(reader)
+        };
+        Self { reader }
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<WarcRecord>> {
+        let reader: &mut dyn BufRead = match self.reader {
+            Reader::Plain(ref mut reader) => reader,
+            Reader::Gzip(ref mut reader) => reader,
+        };
+
+        // skip the blank lines separating records
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            if !line.trim().is_empty() {
+                break;
+            }
+        }
+        if !line.starts_with("WARC/") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid WARC record start: {}", line.trim()),
+            ));
+        }
+
+        let mut headers = Vec::new();
+        let mut content_length = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                let (name, value) = (name.trim(), value.trim());
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    content_length = value.parse::<u64>().ok();
+                } else {
+                    headers.push((name.to_string(), value.to_string()));
+                }
+            }
+        }
+
+        let content_length = content_length.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "WARC record without Content-Length",
+            )
+        })?;
+        let mut block = Vec::new();
+        reader.take(content_length).read_to_end(&mut block)?;
+        if (block.len() as u64) < content_length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(Some(WarcRecord { headers, block }))
+    }
+}
+
+impl<R: BufRead> Iterator for WarcReader<R> {
+    type Item = io::Result<WarcRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Serialize a HTTP message from its header lines and body
+fn http_message(header_lines: &[String], body: &[u8]) -> Vec<u8> {
+    let mut message = Vec::new();
+    for line in header_lines {
+        // the stored body has already been de-chunked
+        if let Some((name, _)) = headers::split(line) {
+            if name.eq_ignore_ascii_case("transfer-encoding") {
+                continue;
+            }
+        }
+        message.extend_from_slice(line.as_bytes());
+        message.extend_from_slice(b"\r\n");
+    }
+    message.extend_from_slice(b"\r\n");
+    message.extend_from_slice(body);
+    message
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Generate a random (version 4) UUID URN
+fn record_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let random = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.finish()
+    };
+    let high = (random() & 0xffff_ffff_ffff_0fff) | 0x4000;
+    let low = (random() & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+
+    format!(
+        "<urn:uuid:{:08x}-{:04x}-{:04x}-{:04x}-{:012x}>",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http() -> HTTP {
+        HTTP {
+            ip: "127.0.0.1".to_string(),
+            transfer_time: Duration::from_millis(12),
+            redirect_count: 0,
+            content_type: "text/html".to_string(),
+            response_code: 200,
+            headers: vec![
+                "HTTP/1.1 200 OK".to_string(),
+                "Content-Type: text/html".to_string(),
+                "Transfer-Encoding: chunked".to_string(),
+            ],
+            url: "http://example.org/".to_string(),
+            body: "<title>Archived</title><a href='/next'>Next</a>".to_string(),
+            raw_body: None,
+            request_time: SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_209_800),
+            exchanges: Vec::new(),
+        }
+    }
+
+    fn roundtrip(gzip: bool) {
+        let http = http();
+        let html = HTML::from_string(http.body.clone(), Some(http.url.clone())).unwrap();
+        let webpage = Webpage {
+            http,
+            html,
+            manifest: None,
+        };
+
+        let mut writer = WarcWriter::new(Vec::new(), gzip);
+        writer.write_warcinfo(&[("operator", "test")]).unwrap();
+        writer.write_webpage(&webpage).unwrap();
+        let archive = writer.into_inner();
+        assert_eq!(archive.starts_with(&[0x1f, 0x8b]), gzip);
+
+        let records: Vec<_> = WarcReader::new(&archive[..])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let types: Vec<_> = records.iter().map(|r| r.record_type().unwrap()).collect();
+        assert_eq!(types, ["warcinfo", "response", "metadata"]);
+        assert!(records[0].record_id().unwrap().starts_with("<urn:uuid:"));
+        assert_eq!(
+            records[2].header("WARC-Concurrent-To"),
+            records[1].record_id()
+        );
+        assert!(String::from_utf8_lossy(&records[2].block)
+            .contains("outlink: http://example.org/next\r\n"));
+
+        let restored = records[1].to_webpage().unwrap().unwrap();
+        assert_eq!(restored.http.response_code, 200);
+        assert_eq!(restored.http.ip, "127.0.0.1");
+        assert_eq!(restored.http.request_time, webpage.http.request_time);
+        assert_eq!(restored.http.headers.len(), 2);
+        assert_eq!(restored.http.body, webpage.http.body);
+        assert_eq!(restored.html.title, Some("Archived".to_string()));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip(false);
+    }
+
+    #[test]
+    fn test_roundtrip_gzip() {
+        roundtrip(true);
+    }
+
+    #[test]
+    fn test_non_utf8_body() {
+        // Latin-1 encoded "café"
+        let latin1 = b"<title>caf\xe9</title>".to_vec();
+        let (body, raw_body) = decode_body(&latin1);
+        let http = HTTP {
+            body,
+            raw_body,
+            ..http()
+        };
+        assert_eq!(http.body, "<title>caf\u{fffd}</title>");
+
+        let mut writer = WarcWriter::new(Vec::new(), false);
+        writer.write_http(&http).unwrap();
+        let archive = writer.into_inner();
+        let record = WarcReader::new(&archive[..]).next().unwrap().unwrap();
+        // the block is read back by its Content-Length
+        assert!(record.block.ends_with(&latin1));
+        assert_eq!(record.to_http().unwrap().body_bytes(), &latin1[..]);
+    }
+}
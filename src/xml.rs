@@ -0,0 +1,90 @@
+//! Helpers for walking XML documents (sitemaps, feeds) parsed into an RcDom
+
+use std::io;
+
+use html5ever::tendril::TendrilSink;
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use xml5ever::driver::{parse_document, XmlParseOpts};
+
+/// Parsed XML document
+pub(crate) struct Document {
+    /// Root element
+    pub root: Handle,
+    // dropping the dom detaches all nodes, so it is kept alongside
+    _dom: RcDom,
+}
+
+/// Parse an XML document
+pub(crate) fn parse(mut input: &[u8]) -> Result<Document, io::Error> {
+    let dom = parse_document(RcDom::default(), XmlParseOpts::default())
+        .from_utf8()
+        .read_from(&mut input)?;
+
+    let root = children(&dom.document).into_iter().next();
+    let root =
+        root.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no XML root element"))?;
+    Ok(Document { root, _dom: dom })
+}
+
+/// Local name of an element, without namespace prefix
+pub(crate) fn name(handle: &Handle) -> &str {
+    match handle.data {
+        NodeData::Element { ref name, .. } => name.local.as_ref(),
+        _ => "",
+    }
+}
+
+/// Child elements
+pub(crate) fn children(handle: &Handle) -> Vec<Handle> {
+    handle
+        .children
+        .borrow()
+        .iter()
+        .filter(|child| matches!(child.data, NodeData::Element { .. }))
+        .cloned()
+        .collect()
+}
+
+/// First child element with the given local name
+pub(crate) fn child(handle: &Handle, local_name: &str) -> Option<Handle> {
+    children(handle)
+        .into_iter()
+        .find(|child| name(child) == local_name)
+}
+
+/// Trimmed text of the first child element with the given local name, if not empty
+pub(crate) fn child_text(handle: &Handle, local_name: &str) -> Option<String> {
+    child(handle, local_name)
+        .map(|child| text(&child))
+        .filter(|text| !text.is_empty())
+}
+
+/// Attribute value by local name
+pub(crate) fn attribute(handle: &Handle, local_name: &str) -> Option<String> {
+    match handle.data {
+        NodeData::Element { ref attrs, .. } => attrs
+            .borrow()
+            .iter()
+            .find(|attr| attr.name.local.as_ref() == local_name)
+            .map(|attr| attr.value.trim().to_string()),
+        _ => None,
+    }
+}
+
+/// All text (and CDATA) inside the element, trimmed
+pub(crate) fn text(handle: &Handle) -> String {
+    let mut output = String::new();
+    let mut stack = vec![handle.clone()];
+    while let Some(handle) = stack.pop() {
+        match handle.data {
+            NodeData::Text { ref contents } => output.push_str(&contents.borrow()),
+            NodeData::Element { .. } => {
+                let children_start = stack.len();
+                stack.extend(handle.children.borrow().iter().cloned());
+                stack[children_start..].reverse();
+            }
+            _ => (),
+        }
+    }
+    output.trim().to_string()
+}
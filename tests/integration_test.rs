@@ -4,7 +4,7 @@ use std::io::{Read, Write};
 use std::net::TcpListener;
 
 #[cfg(feature = "curl")]
-use webpage::{Webpage, WebpageOptions, HTML};
+use webpage::{Client, PemSource, Request, Sitemap, Timeout, Webpage, WebpageOptions, HTML, HTTP};
 
 #[test]
 fn from_file() {
@@ -17,6 +17,39 @@ fn from_file() {
     assert!(html.description.is_none());
 }
 
+#[test]
+#[cfg(feature = "curl")]
+fn from_file_url() {
+    let path = std::fs::canonicalize("tests/data/index.html").unwrap();
+    let url = format!("file://{}", path.display());
+    let webpage = Webpage::from_url(&url, WebpageOptions::default()).unwrap();
+
+    assert_eq!(webpage.http.content_type, "text/html");
+    assert_eq!(webpage.http.response_code, 200);
+    assert_eq!(webpage.html.title, Some("Example Domain".to_string()));
+
+    let missing = Webpage::from_url("file:///does/not/exist.html", WebpageOptions::default());
+    assert_eq!(missing.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn from_data_url() {
+    let url = "data:text/html;charset=utf-8,%3Ctitle%3EInline%3C/title%3E";
+    let webpage = Webpage::from_url(url, WebpageOptions::default()).unwrap();
+    assert_eq!(webpage.http.content_type, "text/html;charset=utf-8");
+    assert_eq!(webpage.html.title, Some("Inline".to_string()));
+
+    // "<title>Encoded</title>"
+    let url = "data:text/html;base64,PHRpdGxlPkVuY29kZWQ8L3RpdGxlPg";
+    let webpage = Webpage::from_url(url, WebpageOptions::default()).unwrap();
+    assert_eq!(webpage.html.title, Some("Encoded".to_string()));
+
+    let http = HTTP::fetch("data:,Hello%2C%20World", WebpageOptions::default()).unwrap();
+    assert_eq!(http.content_type, "text/plain;charset=US-ASCII");
+    assert_eq!(http.body, "Hello, World");
+}
+
 #[test]
 #[ignore]
 #[cfg(feature = "curl")]
@@ -57,3 +90,380 @@ fn test_headers() {
     assert!(request.contains("X-My-Header: 1234\r\n"));
     stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
 }
+
+/// Serve canned HTTP responses on a random local port, one connection per response. Returns the
+/// URL and a handle yielding the raw requests that were received
+#[cfg(feature = "curl")]
+fn serve(responses: &'static [&'static [u8]]) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    let socket = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", socket.local_addr().unwrap());
+    (url, serve_on(socket, responses))
+}
+
+/// Serve canned HTTP responses on the given socket, for responses that refer to its address
+#[cfg(feature = "curl")]
+fn serve_on(
+    socket: TcpListener,
+    responses: &'static [&'static [u8]],
+) -> std::thread::JoinHandle<Vec<String>> {
+    std::thread::spawn(move || {
+        let mut requests = Vec::new();
+        for response in responses {
+            let mut stream = socket.accept().unwrap().0;
+            let mut buf = vec![0; 4096];
+            let mut read = 0;
+            let mut request;
+            loop {
+                let bytes = stream.read(&mut buf[read..]).unwrap();
+                assert_ne!(bytes, 0);
+                read += bytes;
+                request = String::from_utf8_lossy(&buf[..read]).into_owned();
+                if let Some(end) = request.find("\r\n\r\n") {
+                    let length = request[..end]
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .map_or(0, |length| length.parse().unwrap());
+                    if read >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            stream.write_all(response).unwrap();
+            requests.push(request);
+        }
+        requests
+    })
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_tls_options() {
+    let (url, server) =
+        serve(&[b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"]);
+
+    // TLS settings are accepted by curl, and ignored for plain HTTP
+    let mut options = WebpageOptions::default();
+    options.ca_certificates = Some(PemSource::Bytes(b"-----BEGIN CERTIFICATE-----".to_vec()));
+    options.client_certificate = Some(PemSource::Path("client.pem".into()));
+    options.client_key = Some(PemSource::Path("client.key".into()));
+    options.client_key_password = Some("secret".to_string());
+    options.pinned_public_keys =
+        vec!["sha256//YhKJKSzoTt2b5FP18fvpHo7fJYqQCjAa3HWY3tvRMwE=".to_string()];
+
+    let webpage = Webpage::from_url(&url, options);
+    assert!(webpage.is_ok());
+    server.join().unwrap();
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_post_json() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 27\r\n\r\n<title>Created</title>Done.",
+    ]);
+
+    let request = Request::post(&url).json(&serde_json::json!({"name": "webpage"}));
+    let webpage = Webpage::from_request(request, WebpageOptions::default()).unwrap();
+    assert_eq!(webpage.http.response_code, 200);
+    assert_eq!(webpage.html.title, Some("Created".to_string()));
+
+    let request = &server.join().unwrap()[0];
+    assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+    assert!(request.contains("Content-Type: application/json\r\n"));
+    assert!(request.ends_with("\r\n\r\n{\"name\":\"webpage\"}"));
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_head() {
+    let (url, server) =
+        serve(&[b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 1234\r\n\r\n"]);
+
+    let http = Request::head(&url).send(WebpageOptions::default()).unwrap();
+    assert_eq!(http.content_type, "text/html");
+    assert!(http.body.is_empty());
+
+    assert!(server.join().unwrap()[0].starts_with("HEAD / HTTP/1.1\r\n"));
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_robots() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nX-Robots-Tag: googlebot: noindex\r\nContent-Length: 39\r\n\r\n<meta name=\"robots\" content=\"nofollow\">",
+    ]);
+
+    let webpage = Webpage::from_url(&url, WebpageOptions::default()).unwrap();
+    server.join().unwrap();
+
+    assert!(webpage.is_indexable(None));
+    assert!(!webpage.is_indexable(Some("Googlebot")));
+    assert!(!webpage.is_followable(None));
+    assert!(webpage.html.robots.all.nofollow);
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_sitemap() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/xml\r\nContent-Length: 1234\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 42\r\n\r\nhttps://example.org/\nhttps://example.org/a",
+    ]);
+
+    let client = Client::new(WebpageOptions::default());
+    let sitemaps = client.discover_sitemaps(&url).unwrap();
+    assert_eq!(sitemaps, vec![format!("{}sitemap.xml", url)]);
+
+    let sitemap = client.fetch_sitemap(&sitemaps[0]).unwrap();
+    assert_eq!(sitemap.entries.len(), 2);
+    assert_eq!(sitemap.entries[1].loc, "https://example.org/a");
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("GET /robots.txt HTTP/1.1\r\n"));
+    assert!(requests[1].starts_with("HEAD /sitemap.xml HTTP/1.1\r\n"));
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_sitemap_fetch_all() {
+    let socket = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", socket.local_addr().unwrap());
+    let index = format!(
+        "<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\
+         <sitemap><loc>{url}missing.xml</loc></sitemap><sitemap><loc>{url}a.txt</loc></sitemap>\
+         </sitemapindex>"
+    );
+    let index = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/xml\r\nContent-Length: {}\r\n\r\n{}",
+        index.len(),
+        index
+    );
+    let responses: &'static [&'static [u8]] = Box::leak(Box::new([
+        Box::leak(index.into_boxed_str()).as_bytes(),
+        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 20\r\n\r\nhttps://example.org/",
+    ]));
+    let server = serve_on(socket, responses);
+
+    let entries = Sitemap::fetch_all(&format!("{}index.xml", url), WebpageOptions::default());
+    let entries = entries.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].loc, "https://example.org/");
+
+    let requests = server.join().unwrap();
+    assert!(requests[1].starts_with("GET /missing.xml HTTP/1.1\r\n"));
+    assert!(requests[2].starts_with("GET /a.txt HTTP/1.1\r\n"));
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_fetch_feed() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 64\r\n\r\n<link rel=\"alternate\" type=\"application/json\" href=\"/feed.json\">",
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/feed+json\r\nContent-Length: 78\r\n\r\n{\"version\":\"https://jsonfeed.org/version/1.1\",\"items\":[{\"id\":\"1\",\"url\":\"/a\"}]}",
+    ]);
+
+    let webpage = Webpage::from_url(&url, WebpageOptions::default()).unwrap();
+    let feed = webpage
+        .fetch_feed(WebpageOptions::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(feed.items[0].link, Some(format!("{}a", url)));
+
+    let requests = server.join().unwrap();
+    assert!(requests[1].starts_with("GET /feed.json HTTP/1.1\r\n"));
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_oembed() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 153\r\n\r\n<link rel=\"alternate\" type=\"text/xml+oembed\" href=\"/oembed.xml\"><link rel=\"alternate\" type=\"application/json+oembed\" href=\"/oembed?url=a&amp;maxwidth=1\">",
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 48\r\n\r\n{\"type\":\"rich\",\"html\":\"<div></div>\",\"width\":640}",
+    ]);
+
+    let webpage = Webpage::from_url(&url, WebpageOptions::default()).unwrap();
+    assert_eq!(webpage.html.oembed.len(), 2);
+    assert_eq!(webpage.html.oembed[0].url, format!("{}oembed.xml", url));
+
+    let options = WebpageOptions::default();
+    let oembed = webpage
+        .fetch_oembed(Some(640), None, options)
+        .unwrap()
+        .unwrap();
+    assert_eq!(oembed.oembed_type, "rich");
+    assert_eq!(oembed.width, Some(640));
+
+    let requests = server.join().unwrap();
+    assert!(requests[1].starts_with("GET /oembed?url=a&maxwidth=640 HTTP/1.1\r\n"));
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_fetch_manifest() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 45\r\n\r\n<link rel=\"manifest\" href=\"/app.webmanifest\">",
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/manifest+json\r\nContent-Length: 62\r\n\r\n{\"name\":\"App\",\"icons\":[{\"src\":\"/icon.png\",\"sizes\":\"512x512\"}]}",
+    ]);
+
+    let mut webpage = Webpage::from_url(&url, WebpageOptions::default()).unwrap();
+    assert_eq!(
+        webpage.html.manifest,
+        Some(format!("{}app.webmanifest", url))
+    );
+
+    let manifest = webpage.fetch_manifest(WebpageOptions::default()).unwrap();
+    assert_eq!(manifest.unwrap().name, Some("App".to_string()));
+    assert_eq!(
+        webpage.html.best_icon(256).unwrap().url,
+        format!("{}icon.png", url)
+    );
+    assert!(webpage.manifest.is_some());
+
+    server.join().unwrap();
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_har() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 302 Found\r\nLocation: /final?page=2\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 5\r\nConnection: close\r\n\r\nHello",
+    ]);
+
+    let mut options = WebpageOptions::default();
+    options.record_transfer = true;
+    let http = HTTP::fetch(&url, options).unwrap();
+    server.join().unwrap();
+
+    assert_eq!(http.exchanges.len(), 2);
+    assert_eq!(http.exchanges[1].url, format!("{}final?page=2", url));
+
+    let har = http.to_har();
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(har["log"]["version"], "1.2");
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0]["request"]["method"], "GET");
+    assert_eq!(entries[0]["request"]["url"], url);
+    assert_eq!(entries[0]["response"]["status"], 302);
+    assert_eq!(entries[0]["response"]["redirectURL"], "/final?page=2");
+
+    assert_eq!(entries[1]["request"]["queryString"][0]["value"], "2");
+    assert_eq!(entries[1]["response"]["status"], 200);
+    assert_eq!(entries[1]["response"]["content"]["text"], "Hello");
+    assert_eq!(entries[1]["response"]["content"]["mimeType"], "text/html");
+    assert_eq!(entries[1]["serverIPAddress"], "127.0.0.1");
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_client_reuse() {
+    let socket = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", socket.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        // both requests must arrive over a single connection
+        let mut stream = socket.accept().unwrap().0;
+        let mut requests = Vec::new();
+        for response in [
+            &b"HTTP/1.1 200 OK\r\nSet-Cookie: session=abc; Path=/\r\nContent-Length: 0\r\n\r\n"[..],
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"[..],
+        ] {
+            let mut buf = vec![0; 4096];
+            let mut read = 0;
+            while !buf[..read].ends_with(b"\r\n\r\n") {
+                let bytes = stream.read(&mut buf[read..]).unwrap();
+                assert_ne!(bytes, 0);
+                read += bytes;
+            }
+            requests.push(String::from_utf8_lossy(&buf[..read]).into_owned());
+            stream.write_all(response).unwrap();
+        }
+        requests
+    });
+
+    let client = Client::new(WebpageOptions::default());
+    assert!(client.fetch(&url).is_ok());
+    assert_eq!(client.cookies().len(), 1);
+    assert!(client.fetch(&format!("{}second", url)).is_ok());
+
+    let requests = server.join().unwrap();
+    assert!(!requests[0].contains("Cookie:"));
+    assert!(requests[1].starts_with("GET /second HTTP/1.1\r\n"));
+    assert!(requests[1].contains("Cookie: session=abc\r\n"));
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_client_cookie_deleted() {
+    let (url, server) = serve(&[
+        b"HTTP/1.1 200 OK\r\nSet-Cookie: session=abc; Path=/\r\nSet-Cookie: theme=dark; Path=/\r\nContent-Length: 0\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nSet-Cookie: session=; Path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT\r\nContent-Length: 0\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+    ]);
+
+    let client = Client::new(WebpageOptions::default());
+    assert!(client.fetch(&url).is_ok());
+    assert_eq!(client.cookies().len(), 2);
+    assert!(client.fetch(&format!("{}logout", url)).is_ok());
+    let cookies = client.cookies();
+    assert_eq!(cookies.len(), 1);
+    assert!(cookies[0].ends_with("\ttheme\tdark"));
+    assert!(client.fetch(&format!("{}third", url)).is_ok());
+
+    let requests = server.join().unwrap();
+    assert!(requests[2].starts_with("GET /third HTTP/1.1\r\n"));
+    assert!(requests[2].contains("Cookie: theme=dark\r\n"));
+}
+
+/// Accept a single connection, send a partial response and then stall
+#[cfg(feature = "curl")]
+fn serve_stalling() -> String {
+    let socket = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", socket.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let mut stream = socket.accept().unwrap().0;
+        let mut buf = vec![0; 4096];
+        let _ = stream.read(&mut buf).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100000\r\n\r\n<html>")
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(5));
+    });
+    url
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_low_speed_limit() {
+    let url = serve_stalling();
+
+    let mut options = WebpageOptions::default();
+    options.low_speed_limit = Some((1000, std::time::Duration::from_secs(1)));
+    let error = HTTP::fetch(&url, options).unwrap_err();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    let limit = error.get_ref().and_then(|e| e.downcast_ref::<Timeout>());
+    assert_eq!(
+        limit,
+        Some(&Timeout::LowSpeed {
+            bytes_per_second: 1000,
+            duration: std::time::Duration::from_secs(1)
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "curl")]
+fn test_timeout() {
+    let url = serve_stalling();
+
+    let mut options = WebpageOptions::default();
+    options.timeout = std::time::Duration::from_secs(1);
+    options.connect_timeout = Some(std::time::Duration::from_millis(500));
+    let error = HTTP::fetch(&url, options).unwrap_err();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    assert_eq!(error.to_string(), "timeout of 1s exceeded");
+}